@@ -0,0 +1,117 @@
+//! Revision: none of these functions compile, because each breaks one of
+//! the rules about references from chapter 0. Fix each one so it compiles
+//! and passes the tests at the bottom of the file. You shouldn't need to
+//! change the function signatures, except for `greeting` (which can't return
+//! a reference) and `longest` (which needs lifetimes).
+
+pub struct Person {
+    pub name: String,
+    pub age: u32,
+}
+
+/// Adds a copy of the first number to the end of the list.
+pub fn repeat_first(numbers: &mut Vec<i32>) {
+    let first = &numbers[0];
+    numbers.push(*first);
+    println!("Repeated {first}");
+}
+
+/// Swaps two elements of a slice.
+pub fn swap_elements(numbers: &mut [i32], a: usize, b: usize) {
+    let first = &mut numbers[a];
+    let second = &mut numbers[b];
+    std::mem::swap(first, second);
+}
+
+/// Doubles every even number, and adds a `0` to the end for each odd one.
+pub fn double_evens(numbers: &mut Vec<i32>) {
+    for number in numbers.iter_mut() {
+        if *number % 2 == 0 {
+            *number *= 2;
+        } else {
+            numbers.push(0);
+        }
+    }
+}
+
+/// The name of the oldest person, if there are any people.
+pub fn oldest_name(people: &[Person]) -> Option<String> {
+    let oldest = people.iter().max_by_key(|person| person.age)?;
+    Some(oldest.name)
+}
+
+/// Greets someone by name.
+pub fn greeting(name: &str) -> &str {
+    let greeting = format!("Hello, {name}!");
+    &greeting
+}
+
+/// The longer of two strings (or the first, if they're the same length).
+pub fn longest(x: &str, y: &str) -> &str {
+    if y.len() > x.len() {
+        y
+    } else {
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_first() {
+        let mut numbers = vec![1, 2, 3];
+        repeat_first(&mut numbers);
+        assert_eq!(numbers, vec![1, 2, 3, 1]);
+    }
+
+    #[test]
+    fn swaps_elements() {
+        let mut numbers = [1, 2, 3];
+        swap_elements(&mut numbers, 0, 2);
+        assert_eq!(numbers, [3, 2, 1]);
+        swap_elements(&mut numbers, 1, 1);
+        assert_eq!(numbers, [3, 2, 1]);
+    }
+
+    #[test]
+    fn doubles_evens() {
+        let mut numbers = vec![1, 2, 3, 4];
+        double_evens(&mut numbers);
+        assert_eq!(numbers, vec![1, 4, 3, 8, 0, 0]);
+    }
+
+    #[test]
+    fn finds_oldest_name() {
+        let people = vec![
+            Person {
+                name: "Ada".to_string(),
+                age: 36,
+            },
+            Person {
+                name: "Grace".to_string(),
+                age: 85,
+            },
+        ];
+        assert_eq!(oldest_name(&people), Some("Grace".to_string()));
+        // We only borrowed `people`, so it still has every name.
+        assert_eq!(people[1].name, "Grace");
+        assert_eq!(oldest_name(&[]), None);
+    }
+
+    #[test]
+    fn greets() {
+        assert_eq!(greeting("Ferris"), "Hello, Ferris!");
+    }
+
+    #[test]
+    fn finds_longest() {
+        let first = String::from("long string is long");
+        let result = {
+            let second = String::from("xyz");
+            longest(&first, &second).to_string()
+        };
+        assert_eq!(result, "long string is long");
+    }
+}
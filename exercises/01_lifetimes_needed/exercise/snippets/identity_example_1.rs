@@ -0,0 +1,10 @@
+// `identity` has one input reference, so that's the only thing it can return.
+fn identity<'a>(a: &'a i32) -> &'a i32 {
+    a
+}
+
+pub fn example_1() {
+    let x = 4;
+    let x_ref = identity(&x);
+    assert_eq!(*x_ref, 4);
+}
@@ -0,0 +1,193 @@
+//! Which programs break?
+//!
+//! Each file in the `snippets` directory is one of the examples from chapter
+//! 1. For each one, decide whether it could have a dangling reference, and
+//! replace the `todo!()` in its function below with your answer.
+//!
+//! The lifetimes in the snippets are what you'll learn to write in chapter 2;
+//! you don't need to understand them yet. The tests check your answers by
+//! compiling each snippet.
+
+/// Whether a program could end up with a dangling reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dangling {
+    /// However it runs, every reference is valid.
+    Never,
+    /// Whether a reference dangles depends on what happens at runtime.
+    Sometimes,
+    /// However it runs, a reference outlives what it refers to.
+    Always,
+}
+
+/// `snippets/identity_example_1.rs`
+pub fn identity_example_1() -> Dangling {
+    todo!("is snippets/identity_example_1.rs Never, Sometimes or Always dangling?")
+}
+
+/// `snippets/identity_example_2.rs`
+pub fn identity_example_2() -> Dangling {
+    todo!("is snippets/identity_example_2.rs Never, Sometimes or Always dangling?")
+}
+
+/// `snippets/option_or_example_1.rs`
+pub fn option_or_example_1() -> Dangling {
+    todo!("is snippets/option_or_example_1.rs Never, Sometimes or Always dangling?")
+}
+
+/// `snippets/option_or_example_2.rs`
+pub fn option_or_example_2() -> Dangling {
+    todo!("is snippets/option_or_example_2.rs Never, Sometimes or Always dangling?")
+}
+
+/// `snippets/option_or_example_3.rs`
+pub fn option_or_example_3() -> Dangling {
+    todo!("is snippets/option_or_example_3.rs Never, Sometimes or Always dangling?")
+}
+
+/// `snippets/option_or_example_4.rs`
+pub fn option_or_example_4() -> Dangling {
+    todo!("is snippets/option_or_example_4.rs Never, Sometimes or Always dangling?")
+}
+
+/// `snippets/max_of_refs.rs`
+pub fn max_of_refs() -> Dangling {
+    todo!("is snippets/max_of_refs.rs Never, Sometimes or Always dangling?")
+}
+
+/// `snippets/only_if_greater.rs`
+pub fn only_if_greater() -> Dangling {
+    todo!("is snippets/only_if_greater.rs Never, Sometimes or Always dangling?")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::process::Command;
+
+    /// Compiles a snippet, returning rustc's errors if it fails.
+    fn compile(snippet: &str) -> Result<(), String> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let out_dir = std::env::temp_dir().join(format!("ex01-{snippet}-{}", std::process::id()));
+        let output = Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+            .args([
+                "--edition",
+                "2021",
+                "--crate-type",
+                "lib",
+                "--emit",
+                "metadata",
+            ])
+            .args(["--cap-lints", "allow", "--color", "never", "--out-dir"])
+            .arg(&out_dir)
+            .arg(dir.join("snippets").join(format!("{snippet}.rs")))
+            .output()
+            .expect("couldn't run rustc");
+        let _ = std::fs::remove_dir_all(&out_dir);
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+        }
+    }
+
+    /// Checks an answer, first against the compiler (which only accepts
+    /// programs that never dangle), then against the right answer.
+    fn check(snippet: &str, answer: Dangling, expected: Dangling, explanation: &str) {
+        match (compile(snippet), answer) {
+            (Ok(()), Dangling::Never) | (Err(_), Dangling::Sometimes | Dangling::Always) => {}
+            (Ok(()), _) => panic!(
+                "snippets/{snippet}.rs compiles, so it never dangles, but you said \
+                 {answer:?}.\n{explanation}"
+            ),
+            (Err(errors), _) => panic!(
+                "snippets/{snippet}.rs doesn't compile, so it could dangle, but you said \
+                 Never. The compiler says:\n{errors}\n{explanation}"
+            ),
+        }
+        assert!(
+            answer == expected,
+            "snippets/{snippet}.rs is {expected:?} dangling, but you said {answer:?}.\n{explanation}"
+        );
+    }
+
+    #[test]
+    fn identity_example_1() {
+        check(
+            "identity_example_1",
+            super::identity_example_1(),
+            Dangling::Never,
+            "`x` lives until the end of the function, and so does `x_ref`.",
+        );
+    }
+
+    #[test]
+    fn identity_example_2() {
+        check(
+            "identity_example_2",
+            super::identity_example_2(),
+            Dangling::Always,
+            "`x_ref` refers to `x`, which is dropped at the end of the inner block; it's used afterwards, however the program runs.",
+        );
+    }
+
+    #[test]
+    fn option_or_example_1() {
+        check(
+            "option_or_example_1",
+            super::option_or_example_1(),
+            Dangling::Never,
+            "Whichever reference `option_or` returns, `x` and `y` both live until the end of the function.",
+        );
+    }
+
+    #[test]
+    fn option_or_example_2() {
+        check(
+            "option_or_example_2",
+            super::option_or_example_2(),
+            Dangling::Always,
+            "`option_or` returns `&y`, and `y` is dropped at the end of the block, before `answer` is used.",
+        );
+    }
+
+    #[test]
+    fn option_or_example_3() {
+        check(
+            "option_or_example_3",
+            super::option_or_example_3(),
+            Dangling::Never,
+            "`y` lives until the end of the function, so `answer` always refers to something valid.",
+        );
+    }
+
+    #[test]
+    fn option_or_example_4() {
+        check(
+            "option_or_example_4",
+            super::option_or_example_4(),
+            Dangling::Always,
+            "`option_or` returns `&x`, and `x` is dropped at the end of the block, before `answer` is used.",
+        );
+    }
+
+    #[test]
+    fn max_of_refs() {
+        check(
+            "max_of_refs",
+            super::max_of_refs(),
+            Dangling::Sometimes,
+            "If the number read is bigger than `a`, `complex_function` returns a reference to `b`, which is dropped when it returns. Otherwise it returns `a`, which is fine. The compiler can't know which will happen, so it rejects the program.",
+        );
+    }
+
+    #[test]
+    fn only_if_greater() {
+        check(
+            "only_if_greater",
+            super::only_if_greater(),
+            Dangling::Never,
+            "`only_if_greater` only ever returns `number`, never `greater_than`, so whatever number is read, `answer` can't dangle.",
+        );
+    }
+}
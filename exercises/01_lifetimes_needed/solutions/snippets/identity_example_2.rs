@@ -0,0 +1,13 @@
+// `identity` has one input reference, so that's the only thing it can return.
+fn identity<'a>(a: &'a i32) -> &'a i32 {
+    a
+}
+
+pub fn example_2() {
+    let mut x_ref: Option<&i32> = None;
+    {
+        let x = 7;
+        x_ref = Some(identity(&x));
+    }
+    assert_eq!(*x_ref.unwrap(), 7);
+}
@@ -0,0 +1,19 @@
+// Either `a` or `b` could be returned.
+fn max_of_refs<'a>(a: &'a i32, b: &'a i32) -> &'a i32 {
+    if *a > *b {
+        a
+    } else {
+        b
+    }
+}
+
+fn read_number_from_stdin() -> i32 {
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line).unwrap();
+    line.trim().parse().unwrap()
+}
+
+pub fn complex_function(a: &i32) -> &i32 {
+    let b = read_number_from_stdin();
+    max_of_refs(a, &b)
+}
@@ -0,0 +1,23 @@
+// This only ever returns `number`, never `greater_than`.
+fn only_if_greater<'a, 'b>(number: &'a i32, greater_than: &'b i32) -> Option<&'a i32> {
+    if number > greater_than {
+        Some(number)
+    } else {
+        None
+    }
+}
+
+fn read_number_from_stdin() -> i32 {
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line).unwrap();
+    line.trim().parse().unwrap()
+}
+
+pub fn example() {
+    let number = 10;
+    let answer = {
+        let greater_than = read_number_from_stdin();
+        only_if_greater(&number, &greater_than)
+    };
+    println!("{answer:?}");
+}
@@ -0,0 +1,12 @@
+// The contents of `opt` and `otherwise` are both references, and either of
+// them could be returned.
+fn option_or<'a>(opt: Option<&'a i32>, otherwise: &'a i32) -> &'a i32 {
+    opt.unwrap_or(otherwise)
+}
+
+pub fn example_1() {
+    let x = 8;
+    let y = 10;
+    let my_number = Some(&x);
+    assert_eq!(&x, option_or(my_number, &y));
+}
@@ -0,0 +1,13 @@
+// The contents of `opt` and `otherwise` are both references, and either of
+// them could be returned.
+fn option_or<'a>(opt: Option<&'a i32>, otherwise: &'a i32) -> &'a i32 {
+    opt.unwrap_or(otherwise)
+}
+
+pub fn example_2() {
+    let answer = {
+        let y = 4;
+        option_or(None, &y)
+    };
+    assert_eq!(answer, &4);
+}
@@ -0,0 +1,14 @@
+// The contents of `opt` and `otherwise` are both references, and either of
+// them could be returned.
+fn option_or<'a>(opt: Option<&'a i32>, otherwise: &'a i32) -> &'a i32 {
+    opt.unwrap_or(otherwise)
+}
+
+pub fn example_4() {
+    let y = 4;
+    let answer = {
+        let x = 7;
+        option_or(Some(&x), &y)
+    };
+    assert_eq!(answer, &7);
+}
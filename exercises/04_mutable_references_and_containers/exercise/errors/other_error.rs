@@ -0,0 +1,28 @@
+// Exercise Part 1: The Other Error
+//
+// This program compiles. Change the lifetimes in the signature of
+// `insert_value` (and nothing else) so that `main` has both of the errors
+// from the chapter:
+//
+//  - E0499: cannot borrow `my_vec` as mutable more than once at a time
+//  - E0502: cannot borrow `my_vec` as immutable because it is also borrowed as mutable
+//
+// Then, in 50 words or less, explain why the E0502 error occurs.
+
+fn insert_value<'vec_lifetime, 'contents_lifetime>(
+    my_vec: &'vec_lifetime mut Vec<&'contents_lifetime i32>,
+    value: &'contents_lifetime i32,
+) {
+    my_vec.push(value)
+}
+
+fn main() {
+    let mut my_vec: Vec<&i32> = vec![];
+    let val1 = 1;
+    let val2 = 2;
+
+    insert_value(&mut my_vec, &val1);
+    insert_value(&mut my_vec, &val2);
+
+    println!("{my_vec:?}");
+}
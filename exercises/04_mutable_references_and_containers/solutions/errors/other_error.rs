@@ -0,0 +1,24 @@
+// Exercise Part 1: The Other Error
+//
+// Giving everything one lifetime means the first `&mut my_vec` has to live
+// as long as `&val1`, which lives as long as `my_vec`'s contents: until the
+// `println!`. So when `println!` borrows `my_vec` immutably, it is still
+// mutably borrowed, which is exactly what E0502 forbids.
+
+fn insert_value<'one_lifetime>(
+    my_vec: &'one_lifetime mut Vec<&'one_lifetime i32>,
+    value: &'one_lifetime i32,
+) {
+    my_vec.push(value)
+}
+
+fn main() {
+    let mut my_vec: Vec<&i32> = vec![];
+    let val1 = 1;
+    let val2 = 2;
+
+    insert_value(&mut my_vec, &val1);
+    insert_value(&mut my_vec, &val2);
+
+    println!("{my_vec:?}");
+}
@@ -0,0 +1,84 @@
+//! Add lifetimes to the `impl` block below, so that it compiles.
+//!
+//! Chapter 6 describes four ways to write `next_word`. Only some of them
+//! will pass the tests at the bottom of this file: can you work out which,
+//! before trying them?
+
+/// This struct keeps track of where we're up to in the string.
+pub struct WordIterator<'s> {
+    position: usize,
+    string: &'s str,
+}
+
+impl WordIterator {
+    /// Creates a new WordIterator based on a string.
+    pub fn new(string: &str) -> WordIterator {
+        WordIterator {
+            position: 0,
+            string,
+        }
+    }
+
+    /// Gives the next word. `None` if there aren't any words left.
+    pub fn next_word(&mut self) -> Option<&str> {
+        let start_of_word = &self.string[self.position..];
+        let index_of_next_space = start_of_word.find(' ').unwrap_or(start_of_word.len());
+        if !start_of_word.is_empty() {
+            self.position += index_of_next_space + 1;
+            Some(&start_of_word[..index_of_next_space])
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Like the chapter's text, this ends in a space.
+    const TEXT: &str = "Twas brillig, and the slithy toves ";
+
+    #[test]
+    fn gives_every_word() {
+        let text = String::from(TEXT);
+        let mut word_iterator = WordIterator::new(&text);
+        let mut words = vec![];
+        while let Some(word) = word_iterator.next_word() {
+            words.push(word);
+        }
+        assert_eq!(
+            words,
+            vec!["Twas", "brillig,", "and", "the", "slithy", "toves"]
+        );
+    }
+
+    /// This only compiles if the words borrow from the string, not from
+    /// the iterator: otherwise `first` would still be borrowing the iterator
+    /// when we ask it for the second word.
+    #[test]
+    fn keeps_words_across_calls() {
+        let text = String::from(TEXT);
+        let mut word_iterator = WordIterator::new(&text);
+        let first = word_iterator.next_word();
+        let second = word_iterator.next_word();
+        assert_eq!(first, Some("Twas"));
+        assert_eq!(second, Some("brillig,"));
+    }
+
+    /// Likewise, this only compiles if the words can outlive the iterator.
+    #[test]
+    fn words_outlive_the_iterator() {
+        let text = String::from(TEXT);
+        let last = {
+            let mut word_iterator = WordIterator::new(&text);
+            let mut last = None;
+            while let Some(word) = word_iterator.next_word() {
+                last = Some(word);
+            }
+            last
+            // word_iterator is dropped here.
+        };
+        assert_eq!(last, Some("toves"));
+    }
+}
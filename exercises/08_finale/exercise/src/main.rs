@@ -1,16 +1,88 @@
+use std::fmt;
+
 use require_lifetimes::require_lifetimes;
 
 #[derive(Debug, PartialEq, Eq)]
 enum MatcherToken {
-    /// This is just text without anything special.
+    /// This is just text without anything special. It's borrowed straight
+    /// from the pattern, so any escaped characters (like `\.`) still have
+    /// their backslashes.
     RawText(&str),
-    /// This is when text could be any one of multiple
-    /// strings. It looks like `(one|two|three)`, where
-    /// `one`, `two` or `three` are the allowed strings.
-    OneOfText(Vec<&str>),
+    /// This is when text could match any one of multiple
+    /// options. It looks like `(one|two|three)`, where
+    /// `one`, `two` or `three` are the allowed options. Each option is a
+    /// sequence of tokens, so it can contain anything a pattern can
+    /// (including other groups): `(ab(c|d)|e)` matches `abc`, `abd` or `e`.
+    /// A pattern with a `|` outside any group, like `ab|cd`, is one big
+    /// group.
+    Group(Vec<Vec<MatcherToken>>),
     /// This is when you're happy to accept any single character.
     /// It looks like `.`
     WildCard,
+    /// This is when the token before it can be repeated. It looks like
+    /// `*` (any number of times), `+` (at least once), `?` (at most once),
+    /// or `{m,n}` (between `m` and `n` times; `{m}` is exactly `m` times, and
+    /// `{m,}` is at least `m` times). After some raw text, only the last
+    /// character is repeated: `ab*` is `a`, then any number of `b`s.
+    Repeat {
+        inner: Box<MatcherToken>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+/// This is what can be wrong with a pattern. Each error has the whole
+/// pattern, the byte offset of the problem, and the text of the pattern
+/// that's wrong (all borrowed from the pattern).
+#[derive(Debug, PartialEq, Eq)]
+enum MatcherError {
+    /// A `(` without a `)` after it, like `a(b|c`.
+    UnclosedGroup {
+        pattern: &str,
+        offset: usize,
+        text: &str,
+    },
+    /// A group with an empty option, like `(a||b)`, `()` or `a|`.
+    EmptyAlternative {
+        pattern: &str,
+        offset: usize,
+        text: &str,
+    },
+    /// A `)` without a `(` before it, like `ab)`.
+    StrayClose {
+        pattern: &str,
+        offset: usize,
+        text: &str,
+    },
+    /// A repetition with nothing (or another repetition) before it, like
+    /// `*a` or `a**`.
+    BadRepetition {
+        pattern: &str,
+        offset: usize,
+        text: &str,
+    },
+    /// A `{` which doesn't start a valid repetition, like `a{2`, `a{x}` or
+    /// `a{3,2}`.
+    BadBounds {
+        pattern: &str,
+        offset: usize,
+        text: &str,
+    },
+    /// A backslash at the end of the pattern, with nothing to escape.
+    TrailingBackslash {
+        pattern: &str,
+        offset: usize,
+        text: &str,
+    },
+}
+
+impl fmt::Display for MatcherError {
+    /// This should show a message saying what's wrong, then the pattern,
+    /// with carets (`^`) under the part that's wrong. For examples, see the
+    /// test cases below.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        todo!()
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -25,9 +97,10 @@ struct Matcher {
 
 impl Matcher {
     /// This should take a string reference, and return
-    /// an `Matcher` which has parsed that reference.
+    /// an `Matcher` which has parsed that reference (or
+    /// the reason it couldn't).
     #[require_lifetimes]
-    fn new(text: &str) -> Option<Matcher> {
+    fn new(text: &str) -> Result<Matcher, MatcherError> {
         todo!()
     }
 
@@ -45,7 +118,15 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{Matcher, MatcherToken};
+    use super::{Matcher, MatcherError, MatcherToken};
+
+    /// This makes a group whose options are just text, like `(d|e|f)`.
+    macro_rules! one_of {
+        ($($option:expr),*) => {
+            MatcherToken::Group(vec![$(vec![MatcherToken::RawText($option)]),*])
+        };
+    }
+
     #[test]
     fn simple_test() {
         let match_string = "abc(d|e|f).".to_string();
@@ -68,7 +149,7 @@ mod test {
                 result,
                 vec![
                     (&MatcherToken::RawText("abc"), "abc"),
-                    (&MatcherToken::OneOfText(vec!["d", "e", "f"]), "d"),
+                    (&one_of!["d", "e", "f"], "d"),
                     (&MatcherToken::WildCard, "e") // or '💪'
                 ]
             );
@@ -76,10 +157,417 @@ mod test {
         }
     }
 
+    #[test]
+    fn backtracking_test() {
+        let match_string = "(a|ab)c(d|e)".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+
+        {
+            // Taking "a" leaves "bce", which doesn't match "c", so the
+            // matcher has to go back and try "ab" instead.
+            let candidate1 = "abce".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&one_of!["a", "ab"], "ab"),
+                    (&MatcherToken::RawText("c"), "c"),
+                    (&one_of!["d", "e"], "e"),
+                ]
+            );
+            assert_eq!(matcher.most_tokens_matched, 3);
+        }
+
+        {
+            // Nothing matches all of this, so the longest partial match wins.
+            let candidate1 = "abcf".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&one_of!["a", "ab"], "ab"),
+                    (&MatcherToken::RawText("c"), "c"),
+                ]
+            );
+            assert_eq!(matcher.most_tokens_matched, 3);
+        }
+    }
+
+    #[test]
+    fn repetition_test() {
+        let match_string = "ab*c(d|e)+.?".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        let b_star = MatcherToken::Repeat {
+            inner: Box::new(MatcherToken::RawText("b")),
+            min: 0,
+            max: None,
+        };
+        let d_or_e_plus = MatcherToken::Repeat {
+            inner: Box::new(one_of!["d", "e"]),
+            min: 1,
+            max: None,
+        };
+        let maybe_any = MatcherToken::Repeat {
+            inner: Box::new(MatcherToken::WildCard),
+            min: 0,
+            max: Some(1),
+        };
+
+        {
+            let candidate1 = "abbbcdedx".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&MatcherToken::RawText("a"), "a"),
+                    (&b_star, "bbb"),
+                    (&MatcherToken::RawText("c"), "c"),
+                    (&d_or_e_plus, "ded"),
+                    (&maybe_any, "x"),
+                ]
+            );
+            assert_eq!(matcher.most_tokens_matched, 5);
+        }
+
+        {
+            // A repetition can match nothing at all.
+            let candidate1 = "acd".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&MatcherToken::RawText("a"), "a"),
+                    (&b_star, ""),
+                    (&MatcherToken::RawText("c"), "c"),
+                    (&d_or_e_plus, "d"),
+                    (&maybe_any, ""),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn counted_repetition_test() {
+        let match_string = "(ab|a){2}b".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        let twice = MatcherToken::Repeat {
+            inner: Box::new(one_of!["ab", "a"]),
+            min: 2,
+            max: Some(2),
+        };
+
+        {
+            // "abab" leaves nothing for the last `b`, so the matcher has
+            // to go back and try "aba".
+            let candidate1 = "abab".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![(&twice, "aba"), (&MatcherToken::RawText("b"), "b")]
+            );
+        }
+
+        assert_eq!(
+            Matcher::new("*a"),
+            Err(MatcherError::BadRepetition {
+                pattern: "*a",
+                offset: 0,
+                text: "*"
+            })
+        );
+        assert_eq!(
+            Matcher::new("a**"),
+            Err(MatcherError::BadRepetition {
+                pattern: "a**",
+                offset: 2,
+                text: "*"
+            })
+        );
+        for (pattern, text) in [
+            ("a{3,2}", "{3,2}"),
+            ("a{x}", "{x}"),
+            ("a{2", "{2"),
+            ("a{", "{"),
+        ] {
+            assert_eq!(
+                Matcher::new(pattern),
+                Err(MatcherError::BadBounds {
+                    pattern,
+                    offset: 1,
+                    text
+                })
+            );
+        }
+        assert_eq!(
+            Matcher::new("ab{2").unwrap_err().to_string(),
+            "this isn't a valid number of repetitions\nab{2\n  ^^"
+        );
+    }
+
+    #[test]
+    fn escape_test() {
+        let match_string = r"a\.b".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        assert_eq!(matcher.tokens, vec![MatcherToken::RawText(r"a\.b")]);
+        {
+            // The token's text is still borrowed from the pattern, not
+            // copied out of it.
+            let MatcherToken::RawText(text) = &matcher.tokens[0] else {
+                panic!("expected raw text");
+            };
+            assert_eq!(text.as_ptr(), match_string.as_ptr());
+        }
+
+        {
+            let candidate1 = "a.b".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![(&MatcherToken::RawText(r"a\.b"), "a.b")]);
+        }
+
+        {
+            let candidate1 = "axb".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![]);
+        }
+    }
+
+    #[test]
+    fn escaped_group_test() {
+        let match_string = r"\(x\)(\||\.)+".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+
+        {
+            let candidate1 = "(x)|.|".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&MatcherToken::RawText(r"\(x\)"), "(x)"),
+                    (
+                        &MatcherToken::Repeat {
+                            inner: Box::new(one_of![r"\|", r"\."]),
+                            min: 1,
+                            max: None,
+                        },
+                        "|.|"
+                    ),
+                ]
+            );
+        }
+
+        // A repetition after an escaped character repeats all of it.
+        assert_eq!(
+            Matcher::new(r"a\.*").unwrap().tokens,
+            vec![
+                MatcherToken::RawText("a"),
+                MatcherToken::Repeat {
+                    inner: Box::new(MatcherToken::RawText(r"\.")),
+                    min: 0,
+                    max: None,
+                },
+            ]
+        );
+        assert_eq!(
+            Matcher::new(r"abc\"),
+            Err(MatcherError::TrailingBackslash {
+                pattern: r"abc\",
+                offset: 3,
+                text: r"\"
+            })
+        );
+    }
+
+    #[test]
+    fn nested_group_test() {
+        let match_string = "(ab(c|d)|e)f".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        let group = MatcherToken::Group(vec![
+            vec![MatcherToken::RawText("ab"), one_of!["c", "d"]],
+            vec![MatcherToken::RawText("e")],
+        ]);
+        assert_eq!(matcher.tokens[0], group);
+
+        {
+            // The tokens inside a group still borrow from the pattern.
+            let MatcherToken::Group(options) = &matcher.tokens[0] else {
+                panic!("expected a group");
+            };
+            let MatcherToken::RawText(text) = &options[0][0] else {
+                panic!("expected raw text");
+            };
+            assert_eq!(text.as_ptr(), match_string[1..].as_ptr());
+        }
+
+        {
+            let candidate1 = "abdf".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![(&group, "abd"), (&MatcherToken::RawText("f"), "f")]
+            );
+            // The matched text borrows from the candidate.
+            assert_eq!(result[1].1.as_ptr(), candidate1[3..].as_ptr());
+        }
+
+        {
+            let candidate1 = "ef".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![(&group, "e"), (&MatcherToken::RawText("f"), "f")]
+            );
+        }
+
+        {
+            let candidate1 = "abef".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![]);
+        }
+
+        // Repeating a group repeats everything in it.
+        let mut matcher = Matcher::new("(a(b|c))+x").unwrap();
+        let candidate1 = "abacx".to_string();
+        let result = matcher.match_string(&candidate1);
+        assert_eq!(result[0].1, "abac");
+        assert_eq!(result[1], (&MatcherToken::RawText("x"), "x"));
+    }
+
+    #[test]
+    fn alternation_test() {
+        let match_string = "ab|c.d".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        let either = MatcherToken::Group(vec![
+            vec![MatcherToken::RawText("ab")],
+            vec![
+                MatcherToken::RawText("c"),
+                MatcherToken::WildCard,
+                MatcherToken::RawText("d"),
+            ],
+        ]);
+        assert_eq!(matcher.tokens.len(), 1);
+        assert_eq!(matcher.tokens[0], either);
+
+        {
+            // The second option is borrowed from the middle of the pattern.
+            let MatcherToken::Group(options) = &matcher.tokens[0] else {
+                panic!("expected a group");
+            };
+            let MatcherToken::RawText(text) = &options[1][0] else {
+                panic!("expected raw text");
+            };
+            assert_eq!(text.as_ptr(), match_string[3..].as_ptr());
+        }
+
+        {
+            let candidate1 = "cxdy".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![(&either, "cxd")]);
+            assert_eq!(result[0].1.as_ptr(), candidate1.as_ptr());
+        }
+
+        {
+            let candidate1 = "ab".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![(&either, "ab")]);
+        }
+    }
+
     #[test]
     fn broken_matcher() {
         let match_string = "abc(d|e|f.".to_string();
-        let matcher = Matcher::new(&match_string);
-        assert_eq!(matcher, None);
+        let error = Matcher::new(&match_string).unwrap_err();
+        assert_eq!(
+            error,
+            MatcherError::UnclosedGroup {
+                pattern: &match_string,
+                offset: 3,
+                text: "(d|e|f."
+            }
+        );
+        // The error borrows the offending text from the pattern.
+        let MatcherError::UnclosedGroup { text, .. } = error else {
+            unreachable!()
+        };
+        assert!(std::ptr::eq(text.as_ptr(), match_string[3..].as_ptr()));
+        assert_eq!(
+            error.to_string(),
+            "this group is never closed\nabc(d|e|f.\n   ^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn matcher_errors() {
+        let error = Matcher::new("a(b||c)").unwrap_err();
+        assert_eq!(
+            error,
+            MatcherError::EmptyAlternative {
+                pattern: "a(b||c)",
+                offset: 3,
+                text: "||"
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "this group has an empty option\na(b||c)\n   ^^"
+        );
+
+        assert_eq!(
+            Matcher::new("x()"),
+            Err(MatcherError::EmptyAlternative {
+                pattern: "x()",
+                offset: 1,
+                text: "()"
+            })
+        );
+
+        assert_eq!(
+            Matcher::new("a|"),
+            Err(MatcherError::EmptyAlternative {
+                pattern: "a|",
+                offset: 1,
+                text: "|"
+            })
+        );
+
+        // Groups can be nested, but each of them has to be closed (even
+        // if the last option is empty).
+        for (pattern, offset) in [("a(", 1), ("abc(", 3), ("(", 0), ("a(b|", 1)] {
+            assert_eq!(
+                Matcher::new(pattern),
+                Err(MatcherError::UnclosedGroup {
+                    pattern,
+                    offset,
+                    text: &pattern[offset..]
+                })
+            );
+        }
+        assert_eq!(
+            Matcher::new("(a|(b|c)"),
+            Err(MatcherError::UnclosedGroup {
+                pattern: "(a|(b|c)",
+                offset: 0,
+                text: "(a|(b|c)"
+            })
+        );
+
+        let error = Matcher::new("ab)c").unwrap_err();
+        assert_eq!(
+            error,
+            MatcherError::StrayClose {
+                pattern: "ab)c",
+                offset: 2,
+                text: ")"
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "this `)` doesn't close a group\nab)c\n  ^"
+        );
+
+        // The carets line up with characters, not bytes.
+        assert_eq!(
+            Matcher::new("é*ü**").unwrap_err().to_string(),
+            "this repetition doesn't repeat anything\né*ü**\n    ^"
+        );
     }
 }
@@ -1,16 +1,148 @@
+use std::fmt;
+
 use require_lifetimes::require_lifetimes;
 
 #[derive(Debug, PartialEq, Eq)]
 enum MatcherToken<'a> {
-    /// This is just text without anything special.
+    /// This is just text without anything special. It's borrowed straight
+    /// from the pattern, so any escaped characters (like `\.`) still have
+    /// their backslashes.
     RawText(&'a str),
-    /// This is when text could be any one of multiple
-    /// strings. It looks like `(one|two|three)`, where
-    /// `one`, `two` or `three` are the allowed strings.
-    OneOfText(Vec<&'a str>),
+    /// This is when text could match any one of multiple
+    /// options. It looks like `(one|two|three)`, where
+    /// `one`, `two` or `three` are the allowed options. Each option is a
+    /// sequence of tokens, so it can contain anything a pattern can
+    /// (including other groups): `(ab(c|d)|e)` matches `abc`, `abd` or `e`.
+    /// A pattern with a `|` outside any group, like `ab|cd`, is one big
+    /// group.
+    Group(Vec<Vec<MatcherToken<'a>>>),
     /// This is when you're happy to accept any single character.
     /// It looks like `.`
     WildCard,
+    /// This is when the token before it can be repeated. It looks like
+    /// `*` (any number of times), `+` (at least once), `?` (at most once),
+    /// or `{m,n}` (between `m` and `n` times; `{m}` is exactly `m` times, and
+    /// `{m,}` is at least `m` times). After some raw text, only the last
+    /// character is repeated: `ab*` is `a`, then any number of `b`s.
+    Repeat {
+        inner: Box<MatcherToken<'a>>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+/// This is what can be wrong with a pattern. Each error has the whole
+/// pattern, the byte offset of the problem, and the text of the pattern
+/// that's wrong (all borrowed from the pattern).
+#[derive(Debug, PartialEq, Eq)]
+enum MatcherError<'a> {
+    /// A `(` without a `)` after it, like `a(b|c`.
+    UnclosedGroup {
+        pattern: &'a str,
+        offset: usize,
+        text: &'a str,
+    },
+    /// A group with an empty option, like `(a||b)`, `()` or `a|`.
+    EmptyAlternative {
+        pattern: &'a str,
+        offset: usize,
+        text: &'a str,
+    },
+    /// A `)` without a `(` before it, like `ab)`.
+    StrayClose {
+        pattern: &'a str,
+        offset: usize,
+        text: &'a str,
+    },
+    /// A repetition with nothing (or another repetition) before it, like
+    /// `*a` or `a**`.
+    BadRepetition {
+        pattern: &'a str,
+        offset: usize,
+        text: &'a str,
+    },
+    /// A `{` which doesn't start a valid repetition, like `a{2`, `a{x}` or
+    /// `a{3,2}`.
+    BadBounds {
+        pattern: &'a str,
+        offset: usize,
+        text: &'a str,
+    },
+    /// A backslash at the end of the pattern, with nothing to escape.
+    TrailingBackslash {
+        pattern: &'a str,
+        offset: usize,
+        text: &'a str,
+    },
+}
+
+impl<'a> MatcherError<'a> {
+    /// This describes the error, and gives the pattern, the offset and the
+    /// offending text.
+    #[require_lifetimes]
+    fn parts<'b>(&'b self) -> (&'static str, &'a str, usize, &'a str) {
+        let (message, pattern, offset, text) = match self {
+            MatcherError::UnclosedGroup {
+                pattern,
+                offset,
+                text,
+            } => ("this group is never closed", pattern, offset, text),
+            MatcherError::EmptyAlternative {
+                pattern,
+                offset,
+                text,
+            } => ("this group has an empty option", pattern, offset, text),
+            MatcherError::StrayClose {
+                pattern,
+                offset,
+                text,
+            } => ("this `)` doesn't close a group", pattern, offset, text),
+            MatcherError::BadRepetition {
+                pattern,
+                offset,
+                text,
+            } => (
+                "this repetition doesn't repeat anything",
+                pattern,
+                offset,
+                text,
+            ),
+            MatcherError::BadBounds {
+                pattern,
+                offset,
+                text,
+            } => (
+                "this isn't a valid number of repetitions",
+                pattern,
+                offset,
+                text,
+            ),
+            MatcherError::TrailingBackslash {
+                pattern,
+                offset,
+                text,
+            } => (
+                "there's nothing after this backslash to escape",
+                pattern,
+                offset,
+                text,
+            ),
+        };
+        (message, pattern, *offset, text)
+    }
+}
+
+impl fmt::Display for MatcherError<'_> {
+    /// This shows the message, then the pattern, with carets under the part
+    /// that's wrong.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (message, pattern, offset, text) = self.parts();
+        let indent = pattern[..offset].chars().count();
+        let carets = text.chars().count().max(1);
+        writeln!(f, "{message}")?;
+        writeln!(f, "{pattern}")?;
+        write!(f, "{}{}", " ".repeat(indent), "^".repeat(carets))
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -25,90 +157,341 @@ struct Matcher<'a> {
 
 impl<'a> Matcher<'a> {
     /// This should take a string reference, and return
-    /// an `Matcher` which has parsed that reference.
+    /// an `Matcher` which has parsed that reference (or
+    /// the reason it couldn't).
     #[require_lifetimes]
-    fn new(text: &'a str) -> Option<Matcher<'a>> {
-        let mut tokens: Vec<MatcherToken> = vec![];
-        let mut text_left = text;
-        loop {
-            if text_left.is_empty() {
-                break;
-            } else if text_left.starts_with('.') {
-                tokens.push(MatcherToken::WildCard);
-                text_left = &text_left[1..];
-            } else if text_left.starts_with('(') {
-                let first_close = text_left.find(')')?;
-                let (options, leftover) = text_left.split_at(first_close);
-                tokens.push(MatcherToken::OneOfText(options[1..].split('|').collect()));
-                text_left = &leftover[1..];
-            } else {
-                let first_wc = text_left.find('.').unwrap_or(text_left.len());
-                let first_one_of = text_left.find('(').unwrap_or(text_left.len());
-                let first_token = first_wc.min(first_one_of);
-                tokens.push(MatcherToken::RawText(&text_left[..first_token]));
-                text_left = &text_left[first_token..];
-            }
+    fn new(text: &'a str) -> Result<Matcher<'a>, MatcherError<'a>> {
+        let (mut options, end) = Self::parse_options(text, 0, false)?;
+        if end < text.len() {
+            // Only a `)` stops the options before the end of the pattern.
+            return Err(MatcherError::StrayClose {
+                pattern: text,
+                offset: end,
+                text: &text[end..end + 1],
+            });
         }
+        let tokens = if options.len() == 1 {
+            options.pop().unwrap()
+        } else {
+            vec![MatcherToken::Group(options)]
+        };
 
         eprintln!("{tokens:?}");
 
-        Some(Matcher {
+        Ok(Matcher {
             text,
             tokens,
             most_tokens_matched: 0,
         })
     }
 
+    /// This parses options separated by `|`, starting at byte `start` of
+    /// the pattern, up to a `)` or the end of the pattern. It returns the
+    /// options, and where it stopped.
+    #[require_lifetimes]
+    fn parse_options(
+        pattern: &'a str,
+        start: usize,
+        in_group: bool,
+    ) -> Result<(Vec<Vec<MatcherToken<'a>>>, usize), MatcherError<'a>> {
+        let mut options = vec![];
+        let mut option_start = start;
+        loop {
+            let (option, end) = Self::parse_sequence(pattern, option_start)?;
+            let more = pattern[end..].starts_with('|');
+            // A group which runs off the end of the pattern is unclosed,
+            // whatever its last option is (the caller reports that).
+            let unclosed = in_group && end == pattern.len();
+            // An empty pattern is fine, but an empty option isn't.
+            if option.is_empty() && !unclosed && (in_group || more || !options.is_empty()) {
+                // This points at the `(`, `|` or `)` either side of it.
+                let offset = option_start.saturating_sub(1);
+                return Err(MatcherError::EmptyAlternative {
+                    pattern,
+                    offset,
+                    text: &pattern[offset..(option_start + 1).min(pattern.len())],
+                });
+            }
+            options.push(option);
+            if !more {
+                return Ok((options, end));
+            }
+            option_start = end + 1;
+        }
+    }
+
+    /// This parses tokens, starting at byte `start` of the pattern, up to a
+    /// `|`, a `)` or the end of the pattern. It returns the tokens, and
+    /// where it stopped.
+    #[require_lifetimes]
+    fn parse_sequence(
+        pattern: &'a str,
+        start: usize,
+    ) -> Result<(Vec<MatcherToken<'a>>, usize), MatcherError<'a>> {
+        let mut tokens: Vec<MatcherToken> = vec![];
+        let mut offset = start;
+        loop {
+            let text_left = &pattern[offset..];
+            if text_left.is_empty() || text_left.starts_with(['|', ')']) {
+                return Ok((tokens, offset));
+            } else if text_left.starts_with('.') {
+                tokens.push(MatcherToken::WildCard);
+                offset += 1;
+            } else if text_left.starts_with('(') {
+                let (options, end) = Self::parse_options(pattern, offset + 1, true)?;
+                if !pattern[end..].starts_with(')') {
+                    return Err(MatcherError::UnclosedGroup {
+                        pattern,
+                        offset,
+                        text: text_left,
+                    });
+                }
+                tokens.push(MatcherToken::Group(options));
+                offset = end + 1;
+            } else if let Some((min, max, length)) = repetition(text_left) {
+                // This repeats the token before it (which can't be another
+                // repetition).
+                match tokens.pop() {
+                    None | Some(MatcherToken::Repeat { .. }) => {
+                        return Err(MatcherError::BadRepetition {
+                            pattern,
+                            offset,
+                            text: &text_left[..length],
+                        });
+                    }
+                    Some(inner) => tokens.push(MatcherToken::Repeat {
+                        inner: Box::new(inner),
+                        min,
+                        max,
+                    }),
+                }
+                offset += length;
+            } else {
+                let first_token =
+                    find_unescaped(text_left, &['.', '(', ')', '|', '*', '+', '?', '{', '\\'])
+                        .unwrap_or(text_left.len());
+                let mut raw_text = &text_left[..first_token];
+                if text_left.starts_with('{') {
+                    // This is a `{` which doesn't start a valid repetition.
+                    let close = text_left
+                        .find('}')
+                        .map_or(text_left.len(), |close| close + 1);
+                    return Err(MatcherError::BadBounds {
+                        pattern,
+                        offset,
+                        text: &text_left[..close],
+                    });
+                } else if raw_text.is_empty() {
+                    return Err(MatcherError::TrailingBackslash {
+                        pattern,
+                        offset,
+                        text: text_left,
+                    });
+                }
+                // A repetition only repeats the last character of some
+                // text (which might be escaped), so that character needs a
+                // token of its own.
+                if repetition(&text_left[first_token..]).is_some() {
+                    let (last_char, _, _) = *pattern_chars(raw_text).last().unwrap();
+                    if last_char > 0 {
+                        tokens.push(MatcherToken::RawText(&raw_text[..last_char]));
+                        raw_text = &raw_text[last_char..];
+                    }
+                }
+                tokens.push(MatcherToken::RawText(raw_text));
+                offset += first_token;
+            }
+        }
+    }
+
     /// This should take a string, and return a vector of tokens, and the corresponding part
     /// of the given string. For examples, see the test cases below.
     #[require_lifetimes]
     fn match_string<'b, 'c>(&'b mut self, string: &'c str) -> Vec<(&'b MatcherToken<'a>, &'c str)> {
-        let mut string_left = string;
-        let mut answer = vec![];
+        let answer = Self::match_tokens(&self.tokens, string);
+        if answer.len() > self.most_tokens_matched {
+            self.most_tokens_matched = answer.len();
+        }
 
-        'outer_loop: for token in self.tokens.iter() {
-            if string_left.is_empty() {
-                break;
+        answer
+    }
+
+    /// This matches as many of `tokens` as it can against the start of
+    /// `string`. If a token could match more than one way, it tries each
+    /// of them in turn: it returns the first way of matching every
+    /// token, or if there isn't one, the way that matched the most tokens.
+    #[require_lifetimes]
+    fn match_tokens<'b, 'c>(
+        tokens: &'b [MatcherToken<'a>],
+        string: &'c str,
+    ) -> Vec<(&'b MatcherToken<'a>, &'c str)> {
+        let Some((token, tokens_left)) = tokens.split_first() else {
+            return vec![];
+        };
+
+        let mut best = vec![];
+        for length in token.match_lengths(string) {
+            let (matched, string_left) = string.split_at(length);
+            let mut answer = vec![(token, matched)];
+            answer.extend(Self::match_tokens(tokens_left, string_left));
+            if answer.len() == tokens.len() {
+                return answer;
+            }
+            if answer.len() > best.len() {
+                best = answer;
             }
-            match token {
-                MatcherToken::WildCard => {
-                    // Getting the number of bytes of the first
-                    // character of a str is tricky.  However, because
-                    // we've already verified that string_left is not
-                    // empty, we can use chars().next().unwrap() to
-                    // get the first char and then use len_utf8() to
-                    // find out how many bytes it takes up in a str.
-                    let byte_offset = string_left.chars().next().unwrap().len_utf8();
-                    answer.push((token, &string_left[..byte_offset]));
-                    string_left = &string_left[byte_offset..];
+        }
+        best
+    }
+}
+
+impl<'a> MatcherToken<'a> {
+    /// This returns how many bytes at the start of `string` this token
+    /// could match, best first (repetitions are greedy, so the longest
+    /// run is tried first).
+    fn match_lengths(&self, string: &str) -> Vec<usize> {
+        match self {
+            MatcherToken::WildCard => {
+                // Getting the number of bytes of the first
+                // character of a str is tricky.  However, we
+                // can use chars().next() to get the first char
+                // (if there is one) and then use len_utf8() to
+                // find out how many bytes it takes up in a str.
+                match string.chars().next() {
+                    Some(first_char) => vec![first_char.len_utf8()],
+                    None => vec![],
                 }
-                MatcherToken::OneOfText(options) => {
-                    for start in options {
-                        if string_left.starts_with(start) {
-                            answer.push((token, &string_left[..start.len()]));
-                            string_left = &string_left[start.len()..];
-                            continue 'outer_loop;
+            }
+            MatcherToken::Group(options) => {
+                let mut lengths = vec![];
+                for option in options {
+                    for length in sequence_lengths(option, string) {
+                        if !lengths.contains(&length) {
+                            lengths.push(length);
                         }
                     }
-                    break;
                 }
-                MatcherToken::RawText(text) => {
-                    if string_left.starts_with(text) {
-                        answer.push((token, &string_left[..text.len()]));
-                        string_left = &string_left[text.len()..];
-                        continue;
-                    } else {
+                lengths
+            }
+            MatcherToken::RawText(text) => match_escaped(text, string).into_iter().collect(),
+            MatcherToken::Repeat { inner, min, max } => {
+                // `runs[n]` is every length `n` repetitions could match.
+                let mut runs = vec![vec![0]];
+                while max.is_none_or(|max| runs.len() <= max) {
+                    let mut longer = vec![];
+                    for &length in runs.last().unwrap() {
+                        for inner_length in inner.match_lengths(&string[length..]) {
+                            // Repeating an empty match won't get anywhere.
+                            if inner_length > 0 && !longer.contains(&(length + inner_length)) {
+                                longer.push(length + inner_length);
+                            }
+                        }
+                    }
+                    if longer.is_empty() {
                         break;
                     }
+                    runs.push(longer);
                 }
+
+                let mut lengths = vec![];
+                for run in runs.iter().skip(*min).rev() {
+                    for &length in run {
+                        if !lengths.contains(&length) {
+                            lengths.push(length);
+                        }
+                    }
+                }
+                lengths
             }
         }
-        if answer.len() > self.most_tokens_matched {
-            self.most_tokens_matched = answer.len();
+    }
+}
+
+/// This returns how many bytes at the start of `string` the whole of
+/// `tokens` could match, best first.
+fn sequence_lengths(tokens: &[MatcherToken], string: &str) -> Vec<usize> {
+    let Some((token, tokens_left)) = tokens.split_first() else {
+        return vec![0];
+    };
+
+    let mut lengths = vec![];
+    for length in token.match_lengths(string) {
+        for length_left in sequence_lengths(tokens_left, &string[length..]) {
+            if !lengths.contains(&(length + length_left)) {
+                lengths.push(length + length_left);
+            }
         }
+    }
+    lengths
+}
 
-        answer
+/// If `text` starts with a repetition (`*`, `+`, `?`, `{m}`, `{m,}` or
+/// `{m,n}`), this returns the least and most times it allows, and how many
+/// bytes long it is.
+fn repetition(text: &str) -> Option<(usize, Option<usize>, usize)> {
+    match text.chars().next()? {
+        '*' => Some((0, None, 1)),
+        '+' => Some((1, None, 1)),
+        '?' => Some((0, Some(1), 1)),
+        '{' => {
+            let close = text.find('}')?;
+            let (min, max) = match text[1..close].split_once(',') {
+                None => {
+                    let times = text[1..close].parse().ok()?;
+                    (times, Some(times))
+                }
+                Some((min, "")) => (min.parse().ok()?, None),
+                Some((min, max)) => (min.parse().ok()?, Some(max.parse().ok()?)),
+            };
+            if max.is_some_and(|max| max < min) {
+                return None;
+            }
+            Some((min, max, close + 1))
+        }
+        _ => None,
+    }
+}
+
+/// This goes through the characters of some pattern text, giving the byte
+/// offset each one starts at, and whether it was escaped with a backslash
+/// (in which case the offset is the backslash's). A backslash at the very
+/// end doesn't escape anything, so it's given as an unescaped `\`.
+fn pattern_chars(text: &str) -> Vec<(usize, char, bool)> {
+    let mut chars = vec![];
+    let mut char_indices = text.char_indices();
+    while let Some((index, c)) = char_indices.next() {
+        if c == '\\' {
+            match char_indices.next() {
+                Some((_, escaped)) => chars.push((index, escaped, true)),
+                None => chars.push((index, c, false)),
+            }
+        } else {
+            chars.push((index, c, false));
+        }
     }
+    chars
+}
+
+/// This finds the first of `chars` in `text` which isn't escaped.
+fn find_unescaped(text: &str, chars: &[char]) -> Option<usize> {
+    pattern_chars(text)
+        .into_iter()
+        .find(|(_, c, escaped)| !escaped && chars.contains(c))
+        .map(|(index, _, _)| index)
+}
+
+/// If `string` starts with the text `pattern` describes (reading escaped
+/// characters as themselves), this returns how many bytes of `string` that
+/// is.
+fn match_escaped(pattern: &str, string: &str) -> Option<usize> {
+    let mut length = 0;
+    for (_, c, _) in pattern_chars(pattern) {
+        if !string[length..].starts_with(c) {
+            return None;
+        }
+        length += c.len_utf8();
+    }
+    Some(length)
 }
 
 fn main() {
@@ -117,7 +500,15 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::{Matcher, MatcherToken};
+    use super::{Matcher, MatcherError, MatcherToken};
+
+    /// This makes a group whose options are just text, like `(d|e|f)`.
+    macro_rules! one_of {
+        ($($option:expr),*) => {
+            MatcherToken::Group(vec![$(vec![MatcherToken::RawText($option)]),*])
+        };
+    }
+
     #[test]
     fn simple_test() {
         let match_string = "abc(d|e|f).".to_string();
@@ -140,7 +531,7 @@ mod test {
                 result,
                 vec![
                     (&MatcherToken::RawText("abc"), "abc"),
-                    (&MatcherToken::OneOfText(vec!["d", "e", "f"]), "d"),
+                    (&one_of!["d", "e", "f"], "d"),
                     (&MatcherToken::WildCard, "e") // or '💪'
                 ]
             );
@@ -148,10 +539,417 @@ mod test {
         }
     }
 
+    #[test]
+    fn backtracking_test() {
+        let match_string = "(a|ab)c(d|e)".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+
+        {
+            // Taking "a" leaves "bce", which doesn't match "c", so the
+            // matcher has to go back and try "ab" instead.
+            let candidate1 = "abce".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&one_of!["a", "ab"], "ab"),
+                    (&MatcherToken::RawText("c"), "c"),
+                    (&one_of!["d", "e"], "e"),
+                ]
+            );
+            assert_eq!(matcher.most_tokens_matched, 3);
+        }
+
+        {
+            // Nothing matches all of this, so the longest partial match wins.
+            let candidate1 = "abcf".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&one_of!["a", "ab"], "ab"),
+                    (&MatcherToken::RawText("c"), "c"),
+                ]
+            );
+            assert_eq!(matcher.most_tokens_matched, 3);
+        }
+    }
+
+    #[test]
+    fn repetition_test() {
+        let match_string = "ab*c(d|e)+.?".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        let b_star = MatcherToken::Repeat {
+            inner: Box::new(MatcherToken::RawText("b")),
+            min: 0,
+            max: None,
+        };
+        let d_or_e_plus = MatcherToken::Repeat {
+            inner: Box::new(one_of!["d", "e"]),
+            min: 1,
+            max: None,
+        };
+        let maybe_any = MatcherToken::Repeat {
+            inner: Box::new(MatcherToken::WildCard),
+            min: 0,
+            max: Some(1),
+        };
+
+        {
+            let candidate1 = "abbbcdedx".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&MatcherToken::RawText("a"), "a"),
+                    (&b_star, "bbb"),
+                    (&MatcherToken::RawText("c"), "c"),
+                    (&d_or_e_plus, "ded"),
+                    (&maybe_any, "x"),
+                ]
+            );
+            assert_eq!(matcher.most_tokens_matched, 5);
+        }
+
+        {
+            // A repetition can match nothing at all.
+            let candidate1 = "acd".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&MatcherToken::RawText("a"), "a"),
+                    (&b_star, ""),
+                    (&MatcherToken::RawText("c"), "c"),
+                    (&d_or_e_plus, "d"),
+                    (&maybe_any, ""),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn counted_repetition_test() {
+        let match_string = "(ab|a){2}b".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        let twice = MatcherToken::Repeat {
+            inner: Box::new(one_of!["ab", "a"]),
+            min: 2,
+            max: Some(2),
+        };
+
+        {
+            // "abab" leaves nothing for the last `b`, so the matcher has
+            // to go back and try "aba".
+            let candidate1 = "abab".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![(&twice, "aba"), (&MatcherToken::RawText("b"), "b")]
+            );
+        }
+
+        assert_eq!(
+            Matcher::new("*a"),
+            Err(MatcherError::BadRepetition {
+                pattern: "*a",
+                offset: 0,
+                text: "*"
+            })
+        );
+        assert_eq!(
+            Matcher::new("a**"),
+            Err(MatcherError::BadRepetition {
+                pattern: "a**",
+                offset: 2,
+                text: "*"
+            })
+        );
+        for (pattern, text) in [
+            ("a{3,2}", "{3,2}"),
+            ("a{x}", "{x}"),
+            ("a{2", "{2"),
+            ("a{", "{"),
+        ] {
+            assert_eq!(
+                Matcher::new(pattern),
+                Err(MatcherError::BadBounds {
+                    pattern,
+                    offset: 1,
+                    text
+                })
+            );
+        }
+        assert_eq!(
+            Matcher::new("ab{2").unwrap_err().to_string(),
+            "this isn't a valid number of repetitions\nab{2\n  ^^"
+        );
+    }
+
+    #[test]
+    fn escape_test() {
+        let match_string = r"a\.b".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        assert_eq!(matcher.tokens, vec![MatcherToken::RawText(r"a\.b")]);
+        {
+            // The token's text is still borrowed from the pattern, not
+            // copied out of it.
+            let MatcherToken::RawText(text) = &matcher.tokens[0] else {
+                panic!("expected raw text");
+            };
+            assert_eq!(text.as_ptr(), match_string.as_ptr());
+        }
+
+        {
+            let candidate1 = "a.b".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![(&MatcherToken::RawText(r"a\.b"), "a.b")]);
+        }
+
+        {
+            let candidate1 = "axb".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![]);
+        }
+    }
+
+    #[test]
+    fn escaped_group_test() {
+        let match_string = r"\(x\)(\||\.)+".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+
+        {
+            let candidate1 = "(x)|.|".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![
+                    (&MatcherToken::RawText(r"\(x\)"), "(x)"),
+                    (
+                        &MatcherToken::Repeat {
+                            inner: Box::new(one_of![r"\|", r"\."]),
+                            min: 1,
+                            max: None,
+                        },
+                        "|.|"
+                    ),
+                ]
+            );
+        }
+
+        // A repetition after an escaped character repeats all of it.
+        assert_eq!(
+            Matcher::new(r"a\.*").unwrap().tokens,
+            vec![
+                MatcherToken::RawText("a"),
+                MatcherToken::Repeat {
+                    inner: Box::new(MatcherToken::RawText(r"\.")),
+                    min: 0,
+                    max: None,
+                },
+            ]
+        );
+        assert_eq!(
+            Matcher::new(r"abc\"),
+            Err(MatcherError::TrailingBackslash {
+                pattern: r"abc\",
+                offset: 3,
+                text: r"\"
+            })
+        );
+    }
+
+    #[test]
+    fn nested_group_test() {
+        let match_string = "(ab(c|d)|e)f".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        let group = MatcherToken::Group(vec![
+            vec![MatcherToken::RawText("ab"), one_of!["c", "d"]],
+            vec![MatcherToken::RawText("e")],
+        ]);
+        assert_eq!(matcher.tokens[0], group);
+
+        {
+            // The tokens inside a group still borrow from the pattern.
+            let MatcherToken::Group(options) = &matcher.tokens[0] else {
+                panic!("expected a group");
+            };
+            let MatcherToken::RawText(text) = &options[0][0] else {
+                panic!("expected raw text");
+            };
+            assert_eq!(text.as_ptr(), match_string[1..].as_ptr());
+        }
+
+        {
+            let candidate1 = "abdf".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![(&group, "abd"), (&MatcherToken::RawText("f"), "f")]
+            );
+            // The matched text borrows from the candidate.
+            assert_eq!(result[1].1.as_ptr(), candidate1[3..].as_ptr());
+        }
+
+        {
+            let candidate1 = "ef".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(
+                result,
+                vec![(&group, "e"), (&MatcherToken::RawText("f"), "f")]
+            );
+        }
+
+        {
+            let candidate1 = "abef".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![]);
+        }
+
+        // Repeating a group repeats everything in it.
+        let mut matcher = Matcher::new("(a(b|c))+x").unwrap();
+        let candidate1 = "abacx".to_string();
+        let result = matcher.match_string(&candidate1);
+        assert_eq!(result[0].1, "abac");
+        assert_eq!(result[1], (&MatcherToken::RawText("x"), "x"));
+    }
+
+    #[test]
+    fn alternation_test() {
+        let match_string = "ab|c.d".to_string();
+        let mut matcher = Matcher::new(&match_string).unwrap();
+        let either = MatcherToken::Group(vec![
+            vec![MatcherToken::RawText("ab")],
+            vec![
+                MatcherToken::RawText("c"),
+                MatcherToken::WildCard,
+                MatcherToken::RawText("d"),
+            ],
+        ]);
+        assert_eq!(matcher.tokens.len(), 1);
+        assert_eq!(matcher.tokens[0], either);
+
+        {
+            // The second option is borrowed from the middle of the pattern.
+            let MatcherToken::Group(options) = &matcher.tokens[0] else {
+                panic!("expected a group");
+            };
+            let MatcherToken::RawText(text) = &options[1][0] else {
+                panic!("expected raw text");
+            };
+            assert_eq!(text.as_ptr(), match_string[3..].as_ptr());
+        }
+
+        {
+            let candidate1 = "cxdy".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![(&either, "cxd")]);
+            assert_eq!(result[0].1.as_ptr(), candidate1.as_ptr());
+        }
+
+        {
+            let candidate1 = "ab".to_string();
+            let result = matcher.match_string(&candidate1);
+            assert_eq!(result, vec![(&either, "ab")]);
+        }
+    }
+
     #[test]
     fn broken_matcher() {
         let match_string = "abc(d|e|f.".to_string();
-        let matcher = Matcher::new(&match_string);
-        assert_eq!(matcher, None);
+        let error = Matcher::new(&match_string).unwrap_err();
+        assert_eq!(
+            error,
+            MatcherError::UnclosedGroup {
+                pattern: &match_string,
+                offset: 3,
+                text: "(d|e|f."
+            }
+        );
+        // The error borrows the offending text from the pattern.
+        let MatcherError::UnclosedGroup { text, .. } = error else {
+            unreachable!()
+        };
+        assert!(std::ptr::eq(text.as_ptr(), match_string[3..].as_ptr()));
+        assert_eq!(
+            error.to_string(),
+            "this group is never closed\nabc(d|e|f.\n   ^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn matcher_errors() {
+        let error = Matcher::new("a(b||c)").unwrap_err();
+        assert_eq!(
+            error,
+            MatcherError::EmptyAlternative {
+                pattern: "a(b||c)",
+                offset: 3,
+                text: "||"
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "this group has an empty option\na(b||c)\n   ^^"
+        );
+
+        assert_eq!(
+            Matcher::new("x()"),
+            Err(MatcherError::EmptyAlternative {
+                pattern: "x()",
+                offset: 1,
+                text: "()"
+            })
+        );
+
+        assert_eq!(
+            Matcher::new("a|"),
+            Err(MatcherError::EmptyAlternative {
+                pattern: "a|",
+                offset: 1,
+                text: "|"
+            })
+        );
+
+        // Groups can be nested, but each of them has to be closed (even
+        // if the last option is empty).
+        for (pattern, offset) in [("a(", 1), ("abc(", 3), ("(", 0), ("a(b|", 1)] {
+            assert_eq!(
+                Matcher::new(pattern),
+                Err(MatcherError::UnclosedGroup {
+                    pattern,
+                    offset,
+                    text: &pattern[offset..]
+                })
+            );
+        }
+        assert_eq!(
+            Matcher::new("(a|(b|c)"),
+            Err(MatcherError::UnclosedGroup {
+                pattern: "(a|(b|c)",
+                offset: 0,
+                text: "(a|(b|c)"
+            })
+        );
+
+        let error = Matcher::new("ab)c").unwrap_err();
+        assert_eq!(
+            error,
+            MatcherError::StrayClose {
+                pattern: "ab)c",
+                offset: 2,
+                text: ")"
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "this `)` doesn't close a group\nab)c\n  ^"
+        );
+
+        // The carets line up with characters, not bytes.
+        assert_eq!(
+            Matcher::new("é*ü**").unwrap_err().to_string(),
+            "this repetition doesn't repeat anything\né*ü**\n    ^"
+        );
     }
 }
@@ -0,0 +1,67 @@
+//! Brain Teaser 1: this should be another way of implementing exercise 5,
+//! but it doesn't compile. Work out why, then fix `find_difference` without
+//! changing its signature. It has to pass the same tests as exercise 5.
+
+use std::collections::HashSet;
+
+#[derive(Debug, Default)]
+pub struct Difference<'first, 'second> {
+    first_only: Vec<&'first str>,
+    second_only: Vec<&'second str>,
+}
+
+pub fn find_difference<'fst, 'snd>(
+    sentence1: &'fst str,
+    sentence2: &'snd str,
+) -> Difference<'fst, 'snd> {
+    let sentence_1_words: HashSet<&str> = sentence1.split(" ").collect();
+    let sentence_2_words: HashSet<&str> = sentence2.split(" ").collect();
+
+    // Subtracting one `HashSet` from another needs both to have the same
+    // element type, which would make `'fst` and `'snd` the same lifetime.
+    // Checking each word with `contains` doesn't.
+    let mut diff = Difference {
+        first_only: sentence_1_words
+            .iter()
+            .filter(|word| !sentence_2_words.contains(*word))
+            .copied()
+            .collect(),
+        second_only: sentence_2_words
+            .iter()
+            .filter(|word| !sentence_1_words.contains(*word))
+            .copied()
+            .collect(),
+    };
+
+    diff.first_only.sort();
+    diff.second_only.sort();
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn main() {
+        let first_sentence = String::from("I hate the surf and the sand.");
+        let second_sentence = String::from("I love the surf and the sand.");
+
+        let first_only = {
+            let third_sentence = String::from("I love the snow and the sand.");
+            let diff = find_difference(&first_sentence, &third_sentence);
+            diff.first_only
+        };
+
+        assert_eq!(first_only, vec!["hate", "surf"]);
+
+        let second_only = {
+            let third_sentence = String::from("I love the snow and the sand.");
+            let diff = find_difference(&third_sentence, &second_sentence);
+            diff.second_only
+        };
+
+        assert_eq!(second_only, vec!["surf"]);
+    }
+}
@@ -0,0 +1,100 @@
+//! Each of these functions returns a trait object (or an `impl Trait`)
+//! which borrows from one of its arguments, but none of them say so, so
+//! none of them compile. Add the lifetimes each one needs.
+//!
+//! The tests at the bottom of this file keep the returned values around
+//! after some of the arguments are dropped, so a `'static` bound (or a
+//! lifetime shared by every argument) won't do.
+
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+/// A word from a sentence.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Word<'s>(pub &'s str);
+
+impl Describe for Word<'_> {
+    fn describe(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// A word from a sentence, which is described loudly.
+pub struct Shout<'s>(pub &'s str);
+
+impl Describe for Shout<'_> {
+    fn describe(&self) -> String {
+        format!("{}!", self.0.to_uppercase())
+    }
+}
+
+/// The `n`th word of a sentence (or `""` if there aren't enough words).
+pub fn nth_word(sentence: &str, separator: &str, n: usize) -> Box<dyn Describe> {
+    Box::new(Word(sentence.split(separator).nth(n).unwrap_or("")))
+}
+
+/// The last word if `use_last` is set, otherwise the first.
+pub fn choose(words: &[Word], use_last: &bool) -> &dyn Describe {
+    if *use_last {
+        &words[words.len() - 1]
+    } else {
+        &words[0]
+    }
+}
+
+/// Like `nth_word`, but shouts the word.
+pub fn shout_word(sentence: &str, separator: &str, n: usize) -> impl Describe {
+    Shout(sentence.split(separator).nth(n).unwrap_or(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxed_word_outlives_separator() {
+        let sentence = String::from("the quick brown fox");
+        let word = {
+            let separator = String::from(" ");
+            nth_word(&sentence, &separator, 2)
+            // separator is dropped here.
+        };
+        assert_eq!(word.describe(), "brown");
+    }
+
+    #[test]
+    fn chosen_word_outlives_flag() {
+        let sentence = String::from("jumps over the lazy dog");
+        let words: Vec<Word> = sentence.split(' ').map(Word).collect();
+        let chosen = {
+            let use_last = true;
+            choose(&words, &use_last)
+            // use_last is dropped here.
+        };
+        assert_eq!(chosen.describe(), "dog");
+    }
+
+    #[test]
+    fn shout_outlives_separator() {
+        let sentence = String::from("hello, world");
+        let shout = {
+            let separator = String::from(", ");
+            shout_word(&sentence, &separator, 1)
+            // separator is dropped here.
+        };
+        assert_eq!(shout.describe(), "WORLD!");
+    }
+
+    #[test]
+    fn objects_can_be_stored_together() {
+        let sentence = String::from("one two three");
+        let separator = String::from(" ");
+        let described: Vec<Box<dyn Describe + '_>> = vec![
+            nth_word(&sentence, &separator, 0),
+            Box::new(shout_word(&sentence, &separator, 1)),
+        ];
+        let described: Vec<String> = described.iter().map(|d| d.describe()).collect();
+        assert_eq!(described, vec!["one", "TWO!"]);
+    }
+}
@@ -0,0 +1,110 @@
+//! Each of these functions returns a trait object (or an `impl Trait`)
+//! which borrows from one of its arguments, but none of them say so, so
+//! none of them compile. Add the lifetimes each one needs.
+//!
+//! The tests at the bottom of this file keep the returned values around
+//! after some of the arguments are dropped, so a `'static` bound (or a
+//! lifetime shared by every argument) won't do.
+
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+/// A word from a sentence.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Word<'s>(pub &'s str);
+
+impl Describe for Word<'_> {
+    fn describe(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// A word from a sentence, which is described loudly.
+pub struct Shout<'s>(pub &'s str);
+
+impl Describe for Shout<'_> {
+    fn describe(&self) -> String {
+        format!("{}!", self.0.to_uppercase())
+    }
+}
+
+/// The `n`th word of a sentence (or `""` if there aren't enough words).
+///
+/// `Box<dyn Describe>` means `Box<dyn Describe + 'static>`, but the word
+/// borrows from `sentence`. There are two input lifetimes, so `'_` won't do
+/// either: we have to name the one we mean.
+pub fn nth_word<'s>(sentence: &'s str, separator: &str, n: usize) -> Box<dyn Describe + 's> {
+    Box::new(Word(sentence.split(separator).nth(n).unwrap_or("")))
+}
+
+/// The last word if `use_last` is set, otherwise the first.
+///
+/// Behind a reference, `dyn Describe` gets the reference's lifetime, so we
+/// only have to say which argument the reference comes from.
+pub fn choose<'w>(words: &'w [Word], use_last: &bool) -> &'w dyn Describe {
+    if *use_last {
+        &words[words.len() - 1]
+    } else {
+        &words[0]
+    }
+}
+
+/// Like `nth_word`, but shouts the word.
+///
+/// An `impl Trait` (in the 2021 edition) doesn't capture any lifetimes
+/// unless it's told to.
+pub fn shout_word<'s>(sentence: &'s str, separator: &str, n: usize) -> impl Describe + 's {
+    Shout(sentence.split(separator).nth(n).unwrap_or(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxed_word_outlives_separator() {
+        let sentence = String::from("the quick brown fox");
+        let word = {
+            let separator = String::from(" ");
+            nth_word(&sentence, &separator, 2)
+            // separator is dropped here.
+        };
+        assert_eq!(word.describe(), "brown");
+    }
+
+    #[test]
+    fn chosen_word_outlives_flag() {
+        let sentence = String::from("jumps over the lazy dog");
+        let words: Vec<Word> = sentence.split(' ').map(Word).collect();
+        let chosen = {
+            let use_last = true;
+            choose(&words, &use_last)
+            // use_last is dropped here.
+        };
+        assert_eq!(chosen.describe(), "dog");
+    }
+
+    #[test]
+    fn shout_outlives_separator() {
+        let sentence = String::from("hello, world");
+        let shout = {
+            let separator = String::from(", ");
+            shout_word(&sentence, &separator, 1)
+            // separator is dropped here.
+        };
+        assert_eq!(shout.describe(), "WORLD!");
+    }
+
+    #[test]
+    fn objects_can_be_stored_together() {
+        let sentence = String::from("one two three");
+        let separator = String::from(" ");
+        let described: Vec<Box<dyn Describe + '_>> = vec![
+            nth_word(&sentence, &separator, 0),
+            Box::new(shout_word(&sentence, &separator, 1)),
+        ];
+        let described: Vec<String> = described.iter().map(|d| d.describe()).collect();
+        assert_eq!(described, vec!["one", "TWO!"]);
+    }
+}
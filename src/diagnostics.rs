@@ -0,0 +1,354 @@
+//! Compiles code with rustc, and checks which errors it produces.
+//!
+//! Some katas aren't about making code compile, but about making it fail in
+//! a particular way. These are single files (not crates), which we compile
+//! with `rustc --error-format=json`. The kata declares which error codes it
+//! expects, and which item (usually a function) each should be reported in.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Deserialize;
+use syn::spanned::Spanned;
+
+use crate::runner;
+use crate::Result;
+
+/// A file which should fail to compile with particular errors.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ErrorKata {
+    /// The file to compile, relative to the crate.
+    pub file: PathBuf,
+    /// The errors that compiling it should produce.
+    pub expected: Vec<ExpectedError>,
+}
+
+/// An error code, and the item it should be reported in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub struct ExpectedError {
+    pub code: String,
+    pub item: String,
+}
+
+impl fmt::Display for ExpectedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in `{}`", self.code, self.item)
+    }
+}
+
+/// The parts of rustc's JSON diagnostics we care about.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Diagnostic {
+    pub message: String,
+    pub code: Option<DiagnosticCode>,
+    pub level: String,
+    pub spans: Vec<DiagnosticSpan>,
+    pub rendered: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiagnosticCode {
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiagnosticSpan {
+    pub file_name: PathBuf,
+    pub line_start: usize,
+    pub is_primary: bool,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+
+    /// The error code, or `"(no code)"` for errors without one.
+    pub fn code(&self) -> &str {
+        self.code.as_ref().map_or("(no code)", |code| &code.code)
+    }
+
+    pub fn primary_span(&self) -> Option<&DiagnosticSpan> {
+        self.spans.iter().find(|span| span.is_primary)
+    }
+}
+
+/// Compiles a single file as a library, returning its diagnostics.
+///
+/// We only ask for metadata, so this stops before code generation.
+pub fn compile_file(file: &Path) -> Result<Vec<Diagnostic>> {
+    // Each compilation gets its own output directory, so they can run at once.
+    static COMPILATIONS: AtomicUsize = AtomicUsize::new(0);
+    let out_dir = std::env::temp_dir().join(format!(
+        "lifetimekata-rustc-{}-{}",
+        std::process::id(),
+        COMPILATIONS.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&out_dir)?;
+    let output = Command::new(rustc_path())
+        .args([
+            "--edition",
+            "2021",
+            "--crate-type",
+            "lib",
+            "--emit",
+            "metadata",
+        ])
+        .args(["--error-format", "json", "--cap-lints", "allow"])
+        .arg("--out-dir")
+        .arg(&out_dir)
+        .arg(file)
+        .output()?;
+    fs::remove_dir_all(&out_dir)?;
+
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str(line).map_err(Into::into))
+        .collect()
+}
+
+/// Builds a package in the workspace at `root`, returning its diagnostics.
+pub fn compile_package(root: &Path, package: &str) -> Result<Vec<Diagnostic>> {
+    /// One line of `cargo build --message-format=json`.
+    #[derive(Deserialize)]
+    struct CargoMessage {
+        reason: String,
+        message: Option<Diagnostic>,
+    }
+
+    let output = Command::new(runner::cargo_path())
+        .args(["build", "--quiet", "--message-format", "json", "--package"])
+        .arg(package)
+        .current_dir(root)
+        .output()?;
+    let mut diagnostics = vec![];
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let message: CargoMessage = serde_json::from_str(line)?;
+        if message.reason == "compiler-message" {
+            diagnostics.extend(message.message);
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// The rustc used to build us (if we were run by cargo), or the one on the `PATH`.
+pub fn rustc_path() -> String {
+    std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string())
+}
+
+/// The name and line range (one-based, inclusive) of every function in a
+/// file, including those in `impl` blocks and modules. Types, traits and
+/// `impl` blocks are included too (an `impl` block is called, say,
+/// `impl Foo`), for errors which aren't in a function.
+pub fn item_lines(source: &str) -> Result<Vec<(String, usize, usize)>> {
+    fn push(found: &mut Vec<(String, usize, usize)>, name: &syn::Ident, span: proc_macro2::Span) {
+        found.push((name.to_string(), span.start().line, span.end().line));
+    }
+
+    fn visit(items: &[syn::Item], lines: &[&str], found: &mut Vec<(String, usize, usize)>) {
+        for item in items {
+            match item {
+                syn::Item::Fn(function) => push(found, &function.sig.ident, function.span()),
+                syn::Item::Struct(item) => push(found, &item.ident, item.span()),
+                syn::Item::Enum(item) => push(found, &item.ident, item.span()),
+                syn::Item::Trait(item) => push(found, &item.ident, item.span()),
+                syn::Item::Impl(block) => {
+                    // Name the block after its first line, e.g. `impl Foo`.
+                    let span = block.span();
+                    let header = lines.get(span.start().line - 1).copied().unwrap_or("impl");
+                    let name = header.trim().trim_end_matches('{').trim_end();
+                    found.push((name.to_string(), span.start().line, span.end().line));
+                    for impl_item in &block.items {
+                        if let syn::ImplItem::Fn(method) = impl_item {
+                            push(found, &method.sig.ident, method.span());
+                        }
+                    }
+                }
+                syn::Item::Mod(module) => {
+                    if let Some((_, items)) = &module.content {
+                        visit(items, lines, found);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let file = syn::parse_file(source)?;
+    let lines: Vec<&str> = source.lines().collect();
+    let mut found = vec![];
+    visit(&file.items, &lines, &mut found);
+    Ok(found)
+}
+
+/// The innermost function containing a line, or `"(top level)"`.
+fn item_at(items: &[(String, usize, usize)], line: usize) -> String {
+    items
+        .iter()
+        .filter(|(_, start, end)| (*start..=*end).contains(&line))
+        .min_by_key(|(_, start, end)| end - start)
+        .map_or_else(|| "(top level)".to_string(), |(name, _, _)| name.clone())
+}
+
+/// Summarises the errors in some diagnostics, one line per distinct error:
+///
+/// ```text
+/// error[E0499] in `main`: cannot borrow `tuple` as mutable more than once at a time
+/// ```
+///
+/// Paths, line numbers and columns are left out (errors are attributed to
+/// items instead, as in [`compare`]), so this only changes when the errors
+/// themselves do. The files the errors are in are read to find the items;
+/// their paths are relative to `base`.
+pub fn snapshot(base: &Path, diagnostics: &[Diagnostic]) -> Result<String> {
+    let mut items = BTreeMap::new();
+    let mut lines = BTreeSet::new();
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+    {
+        let Some(span) = diagnostic.primary_span().or(diagnostic.spans.first()) else {
+            continue;
+        };
+        if !items.contains_key(&span.file_name) {
+            let source = fs::read_to_string(base.join(&span.file_name))?;
+            items.insert(span.file_name.clone(), item_lines(&source)?);
+        }
+        let code = match &diagnostic.code {
+            Some(code) => format!("error[{}]", code.code),
+            None => "error".to_string(),
+        };
+        lines.insert(format!(
+            "{code} in `{}`: {}\n",
+            item_at(&items[&span.file_name], span.line_start),
+            diagnostic.message
+        ));
+    }
+    Ok(lines.into_iter().collect())
+}
+
+/// Whether to update snapshots to match, rather than checking against them.
+/// This is set with the `LIFETIMEKATA_BLESS` environment variable.
+pub fn blessing() -> bool {
+    std::env::var_os("LIFETIMEKATA_BLESS").is_some()
+}
+
+/// How a file's errors compared to those a kata expected.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ErrorReport {
+    pub missing: Vec<ExpectedError>,
+    pub unexpected: Vec<ExpectedError>,
+}
+
+impl ErrorReport {
+    pub fn passed(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for error in &self.missing {
+            writeln!(f, "expected {error}, but it wasn't reported")?;
+        }
+        for error in &self.unexpected {
+            writeln!(f, "didn't expect {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the errors in some diagnostics with the ones we expected.
+///
+/// Each error is attributed to the innermost function containing its primary
+/// span. Only distinct (code, item) pairs are compared, so an error which is
+/// reported twice in the same function counts once.
+pub fn compare(
+    source: &str,
+    diagnostics: &[Diagnostic],
+    expected: &[ExpectedError],
+) -> Result<ErrorReport> {
+    let items = item_lines(source)?;
+    let found: BTreeSet<ExpectedError> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error() && !diagnostic.spans.is_empty())
+        .map(|diagnostic| {
+            let line = diagnostic.primary_span().map_or(0, |span| span.line_start);
+            ExpectedError {
+                code: diagnostic.code().to_string(),
+                item: item_at(&items, line),
+            }
+        })
+        .collect();
+    let expected: BTreeSet<ExpectedError> = expected.iter().cloned().collect();
+
+    Ok(ErrorReport {
+        missing: expected.difference(&found).cloned().collect(),
+        unexpected: found.difference(&expected).cloned().collect(),
+    })
+}
+
+/// Compiles an error kata from the given crate directory, and compares its
+/// errors to the expected ones. Also returns rustc's rendering of the errors.
+pub fn check_error_kata(crate_dir: &Path, kata: &ErrorKata) -> Result<(ErrorReport, String)> {
+    let file = crate_dir.join(&kata.file);
+    let source = fs::read_to_string(&file)?;
+    let diagnostics = compile_file(&file)?;
+    let rendered = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+        .filter_map(|diagnostic| diagnostic.rendered.as_deref())
+        .collect();
+    Ok((compare(&source, &diagnostics, &kata.expected)?, rendered))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected(code: &str, item: &str) -> ExpectedError {
+        ExpectedError {
+            code: code.to_string(),
+            item: item.to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_errors_in_items() {
+        let dir =
+            std::env::temp_dir().join(format!("lifetimekata-diagnostics-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("dangling.rs");
+        let source = "pub fn fine() {}\n\
+                      \n\
+                      pub fn dangling() {\n\
+                      \x20   let r;\n\
+                      \x20   {\n\
+                      \x20       let x = 1;\n\
+                      \x20       r = &x;\n\
+                      \x20   }\n\
+                      \x20   println!(\"{r}\");\n\
+                      }\n";
+        fs::write(&file, source).unwrap();
+
+        let diagnostics = compile_file(&file).unwrap();
+        let report = compare(source, &diagnostics, &[expected("E0597", "dangling")]).unwrap();
+        assert!(report.passed(), "{report}");
+
+        let report = compare(source, &diagnostics, &[expected("E0597", "fine")]).unwrap();
+        assert_eq!(report.missing, vec![expected("E0597", "fine")]);
+        assert_eq!(report.unexpected, vec![expected("E0597", "dangling")]);
+
+        assert_eq!(
+            snapshot(&dir, &diagnostics).unwrap(),
+            "error[E0597] in `dangling`: `x` does not live long enough\n"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
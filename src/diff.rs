@@ -0,0 +1,574 @@
+//! A lifetime-aware diff between an exercise and its solution.
+//!
+//! Rather than diffing lines, we split both files into Rust tokens and diff
+//! those. This means whitespace and formatting changes (like rustfmt
+//! splitting a long signature across lines) don't show up at all. We then
+//! only show the changes which consist entirely of lifetimes and generic
+//! punctuation (`<'a, 'b>`, `&'a`, `+ '_`), since those are what the
+//! exercises are about. Everything else (like a `todo!()` body being filled
+//! in) is counted, and only shown if asked for.
+
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+
+use crate::exercise::Exercise;
+use crate::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Lifetime,
+    Ident,
+    Punct,
+    Literal,
+    Comment,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'s> {
+    text: &'s str,
+    kind: TokenKind,
+    /// The byte offset of this token in the file.
+    start: usize,
+    /// The (zero-based) line this token starts on.
+    line: usize,
+}
+
+impl Token<'_> {
+    /// Whether this token could be part of a lifetime annotation.
+    fn is_lifetime_syntax(&self) -> bool {
+        match self.kind {
+            TokenKind::Lifetime => true,
+            TokenKind::Punct => matches!(self.text, "<" | ">" | "," | "+" | ":"),
+            TokenKind::Ident => self.text == "where",
+            _ => false,
+        }
+    }
+}
+
+/// Splits Rust source into tokens, skipping whitespace.
+///
+/// This is nowhere near a full Rust lexer, but it understands enough
+/// (comments, string and char literals, lifetimes) that it never splits
+/// one token into several.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut line = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let start_line = line;
+        let c = source[i..].chars().next().unwrap();
+        let kind = if c.is_whitespace() {
+            if c == '\n' {
+                line += 1;
+            }
+            i += c.len_utf8();
+            continue;
+        } else if source[i..].starts_with("//") {
+            i = source[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            TokenKind::Comment
+        } else if source[i..].starts_with("/*") {
+            i = source[i + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| i + 2 + end + 2);
+            TokenKind::Comment
+        } else if c == '"' || is_raw_string(&source[i..]) {
+            i = string_end(source, i);
+            TokenKind::Literal
+        } else if let Some(raw) = source[i..]
+            .strip_prefix("r#")
+            .filter(|raw| ident_len(raw) > 0)
+        {
+            // A raw identifier, like `r#type`.
+            i += 2 + ident_len(raw);
+            TokenKind::Ident
+        } else if c == '\'' {
+            // Either a char literal ('a', '\n') or a lifetime ('a, '_).
+            let rest = &source[i + 1..];
+            let mut chars = rest.char_indices();
+            match (chars.next(), chars.next()) {
+                (Some((_, '\\')), Some((escaped, escaped_char))) => {
+                    // Skip the escaped character (which might be a quote),
+                    // then any digits (as in `\x41` or `\u{1F600}`), up to
+                    // the closing quote.
+                    let digits = escaped + escaped_char.len_utf8();
+                    i += 1
+                        + digits
+                        + rest[digits..]
+                            .find('\'')
+                            .map_or(rest.len() - digits, |end| end + 1);
+                    TokenKind::Literal
+                }
+                (Some((_, _)), Some((end, '\''))) => {
+                    i += 1 + end + 1;
+                    TokenKind::Literal
+                }
+                _ => {
+                    i += 1 + ident_len(rest);
+                    TokenKind::Lifetime
+                }
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            i += ident_len(&source[i..]);
+            if c.is_ascii_digit() {
+                TokenKind::Literal
+            } else {
+                TokenKind::Ident
+            }
+        } else {
+            i += c.len_utf8();
+            TokenKind::Punct
+        };
+        line += source[start..i].matches('\n').count();
+        tokens.push(Token {
+            text: &source[start..i],
+            kind,
+            start,
+            line: start_line,
+        });
+    }
+    tokens
+}
+
+fn ident_len(text: &str) -> usize {
+    text.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len())
+}
+
+/// Whether `text` starts with a raw string literal, like `r"..."` or
+/// `r#"..."#` (rather than a raw identifier, like `r#type`).
+fn is_raw_string(text: &str) -> bool {
+    text.strip_prefix('r')
+        .is_some_and(|raw| raw.trim_start_matches('#').starts_with('"'))
+}
+
+/// Finds the end of the string literal starting at `start`.
+fn string_end(source: &str, start: usize) -> usize {
+    let rest = &source[start..];
+    if let Some(raw) = rest.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let body = 1 + hashes + 1;
+        return rest[body..]
+            .find(&terminator)
+            .map_or(source.len(), |end| start + body + end + terminator.len());
+    }
+    let mut escaped = false;
+    for (offset, c) in rest.char_indices().skip(1) {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return start + offset + 1,
+            _ => escaped = false,
+        }
+    }
+    source.len()
+}
+
+/// Whether two pieces of Rust source are the same, ignoring whitespace.
+pub fn same_tokens(a: &str, b: &str) -> bool {
+    let texts = |source| tokenize(source).into_iter().map(|token| token.text);
+    texts(a).eq(texts(b))
+}
+
+/// One difference between the two token streams.
+#[derive(Debug, Clone, Default)]
+struct Hunk {
+    /// Indices of tokens only in the exercise.
+    removed: Vec<usize>,
+    /// Indices of tokens only in the solution.
+    added: Vec<usize>,
+    /// Which lines of each file this hunk touches. A pure insertion touches
+    /// the line of the token it was inserted after, and vice versa.
+    exercise_lines: (usize, usize),
+    solution_lines: (usize, usize),
+}
+
+impl Hunk {
+    fn is_lifetime_only(&self, exercise: &[Token], solution: &[Token]) -> bool {
+        let tokens = || {
+            let removed = self.removed.iter().map(|&i| &exercise[i]);
+            removed.chain(self.added.iter().map(|&j| &solution[j]))
+        };
+        tokens().all(Token::is_lifetime_syntax)
+            && tokens().any(|token| token.kind == TokenKind::Lifetime)
+    }
+
+    /// Whether two hunks are close enough to be shown together.
+    fn overlaps(&self, other: &Hunk) -> bool {
+        other.exercise_lines.0 <= self.exercise_lines.1
+            || other.solution_lines.0 <= self.solution_lines.1
+    }
+
+    fn merge(&mut self, other: Hunk) {
+        self.removed.extend(other.removed);
+        self.added.extend(other.added);
+        self.exercise_lines.1 = self.exercise_lines.1.max(other.exercise_lines.1);
+        self.solution_lines.1 = self.solution_lines.1.max(other.solution_lines.1);
+    }
+}
+
+/// Diffs two token streams, returning the hunks where they differ.
+///
+/// This is a plain longest-common-subsequence diff, which is fine for
+/// files the size of an exercise.
+fn diff_tokens(exercise: &[Token], solution: &[Token]) -> Vec<Hunk> {
+    let same = |i: usize, j: usize| exercise[i].text == solution[j].text;
+    let (n, m) = (exercise.len(), solution.len());
+
+    // lcs[i][j] is the length of the LCS of exercise[i..] and solution[j..].
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if same(i, j) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut hunks: Vec<Hunk> = vec![];
+    let mut current: Option<Hunk> = None;
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && same(i, j) {
+            hunks.extend(current.take());
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        let hunk = current.get_or_insert_with(|| {
+            let anchor = |tokens: &[Token], index: usize| {
+                index.checked_sub(1).map_or(0, |prev| tokens[prev].line)
+            };
+            let exercise_line = anchor(exercise, i);
+            let solution_line = anchor(solution, j);
+            Hunk {
+                exercise_lines: (exercise_line, exercise_line),
+                solution_lines: (solution_line, solution_line),
+                ..Hunk::default()
+            }
+        });
+        if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            extend_lines(
+                &mut hunk.solution_lines,
+                &solution[j],
+                hunk.added.is_empty(),
+            );
+            hunk.added.push(j);
+            j += 1;
+        } else {
+            extend_lines(
+                &mut hunk.exercise_lines,
+                &exercise[i],
+                hunk.removed.is_empty(),
+            );
+            hunk.removed.push(i);
+            i += 1;
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+fn extend_lines(lines: &mut (usize, usize), token: &Token, first: bool) {
+    let last_line = token.line + token.text.matches('\n').count();
+    if first {
+        *lines = (token.line, last_line);
+    } else {
+        lines.1 = last_line;
+    }
+}
+
+/// Whether to colour the output, or underline changes with `^`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Colour,
+    Plain,
+}
+
+impl Style {
+    /// Colour, unless we're not printing to a terminal or `NO_COLOR` is set.
+    pub fn detect() -> Style {
+        if std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+            Style::Colour
+        } else {
+            Style::Plain
+        }
+    }
+}
+
+/// The rendered differences between one exercise file and its solution.
+#[derive(Debug, Default)]
+pub struct FileDiff {
+    pub rendered: String,
+    /// How many groups of lifetime changes were shown.
+    pub shown: usize,
+    /// How many other changes were left out.
+    pub hidden: usize,
+}
+
+/// Renders the differences between an exercise file and a solution file.
+///
+/// Only lifetime changes are rendered, unless `all` is set.
+pub fn diff_file(exercise: &str, solution: &str, all: bool, style: Style) -> FileDiff {
+    let exercise_tokens = tokenize(exercise);
+    let solution_tokens = tokenize(solution);
+
+    let mut groups: Vec<Hunk> = vec![];
+    let mut file_diff = FileDiff::default();
+    for hunk in diff_tokens(&exercise_tokens, &solution_tokens) {
+        if !all && !hunk.is_lifetime_only(&exercise_tokens, &solution_tokens) {
+            file_diff.hidden += 1;
+            continue;
+        }
+        match groups.last_mut() {
+            Some(last) if last.overlaps(&hunk) => last.merge(hunk),
+            _ => groups.push(hunk),
+        }
+    }
+
+    let exercise_lines: Vec<&str> = exercise.lines().collect();
+    let solution_lines: Vec<&str> = solution.lines().collect();
+    for group in &groups {
+        let (first, last) = group.exercise_lines;
+        file_diff.rendered += &format!("@@ line {} @@\n", first + 1);
+        let changed: Vec<&Token> = group.removed.iter().map(|&i| &exercise_tokens[i]).collect();
+        for line in first..=last {
+            render_line(
+                &mut file_diff.rendered,
+                '-',
+                exercise,
+                &exercise_lines,
+                line,
+                &changed,
+                style,
+            );
+        }
+        let changed: Vec<&Token> = group.added.iter().map(|&j| &solution_tokens[j]).collect();
+        let (first, last) = group.solution_lines;
+        for line in first..=last {
+            render_line(
+                &mut file_diff.rendered,
+                '+',
+                solution,
+                &solution_lines,
+                line,
+                &changed,
+                style,
+            );
+        }
+    }
+    file_diff.shown = groups.len();
+    file_diff
+}
+
+/// Renders one line, highlighting the parts of it covered by `changed`.
+fn render_line(
+    out: &mut String,
+    sign: char,
+    source: &str,
+    lines: &[&str],
+    line: usize,
+    changed: &[&Token],
+    style: Style,
+) {
+    let Some(text) = lines.get(line) else {
+        return;
+    };
+    let line_start = text.as_ptr() as usize - source.as_ptr() as usize;
+    let line_end = line_start + text.len();
+
+    // The byte ranges (relative to this line) which have changed.
+    let highlights: Vec<(usize, usize)> = changed
+        .iter()
+        .filter(|token| token.start < line_end && token.start + token.text.len() > line_start)
+        .map(|token| {
+            let start = token.start.max(line_start) - line_start;
+            let end = (token.start + token.text.len()).min(line_end) - line_start;
+            (start, end)
+        })
+        .collect();
+
+    match style {
+        Style::Colour => {
+            let (colour, highlight) = if sign == '-' {
+                ("31", "1;41")
+            } else {
+                ("32", "1;42")
+            };
+            out.push_str(&format!("\x1b[{colour}m{sign} "));
+            let mut position = 0;
+            for (start, end) in highlights {
+                out.push_str(&text[position..start]);
+                out.push_str(&format!(
+                    "\x1b[{highlight}m{}\x1b[0;{colour}m",
+                    &text[start..end]
+                ));
+                position = end;
+            }
+            out.push_str(&text[position..]);
+            out.push_str("\x1b[0m\n");
+        }
+        Style::Plain => {
+            out.push_str(&format!("{sign} {text}\n"));
+            if !highlights.is_empty() {
+                let mut markers = String::from("  ");
+                for (start, end) in highlights {
+                    let pad = text[..start].chars().count() + 2 - markers.chars().count();
+                    markers.push_str(&" ".repeat(pad));
+                    markers.push_str(&"^".repeat(text[start..end].chars().count()));
+                }
+                out.push_str(&markers);
+                out.push('\n');
+            }
+        }
+    }
+}
+
+/// Prints the lifetime differences between every source file of an exercise
+/// and its solution.
+pub fn diff(root: &Path, exercise: &Exercise, all: bool) -> Result<()> {
+    let style = Style::detect();
+    let exercise_src = root.join(&exercise.dir).join("exercise/src");
+    let solution_src = root.join(&exercise.dir).join("solutions/src");
+
+    let mut files: Vec<_> = fs::read_dir(&solution_src)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<Result<_, _>>()?;
+    files.sort();
+
+    let mut shown = 0;
+    let mut hidden = 0;
+    for file in files
+        .iter()
+        .filter(|file| Path::new(file).extension() == Some("rs".as_ref()))
+    {
+        let Ok(exercise_text) = fs::read_to_string(exercise_src.join(file)) else {
+            continue;
+        };
+        // Doc-tests in the solutions `use` the solution crate, which isn't a
+        // difference the learner cares about.
+        let solution_text = fs::read_to_string(solution_src.join(file))?
+            .replace(&exercise.solution_package, &exercise.package);
+
+        let file_diff = diff_file(&exercise_text, &solution_text, all, style);
+        if file_diff.shown > 0 {
+            println!(
+                "--- {}",
+                exercise.dir.join("exercise/src").join(file).display()
+            );
+            println!(
+                "+++ {}",
+                exercise.dir.join("solutions/src").join(file).display()
+            );
+            print!("{}", file_diff.rendered);
+        }
+        shown += file_diff.shown;
+        hidden += file_diff.hidden;
+    }
+
+    if shown == 0 {
+        println!("Your lifetimes match the solution's.");
+    }
+    match hidden {
+        0 => {}
+        1 => println!("(1 other change, like a function body, is not shown; use `--all` to see it.)"),
+        _ => println!("({hidden} other changes, like function bodies, are not shown; use `--all` to see them.)"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_lifetimes_and_chars() {
+        let tokens = tokenize("fn f<'a>(x: &'a char) -> char { 'x' } // '\\n'");
+        let texts: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.text, token.kind))
+            .collect();
+        assert!(texts.contains(&("'a", TokenKind::Lifetime)));
+        assert!(texts.contains(&("'x'", TokenKind::Literal)));
+        assert_eq!(texts.last(), Some(&("// '\\n'", TokenKind::Comment)));
+
+        let tokens = tokenize(r"'\n'; '\\'; '\''; '\u{1F600}'; fn f<'a>");
+        let texts: Vec<_> = tokens
+            .iter()
+            .filter(|token| token.kind != TokenKind::Punct)
+            .map(|token| (token.text, token.kind))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                (r"'\n'", TokenKind::Literal),
+                (r"'\\'", TokenKind::Literal),
+                (r"'\''", TokenKind::Literal),
+                (r"'\u{1F600}'", TokenKind::Literal),
+                ("fn", TokenKind::Ident),
+                ("f", TokenKind::Ident),
+                ("'a", TokenKind::Lifetime),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_raw_strings_and_identifiers() {
+        let tokens = tokenize(r###"let r#type = r#"a "b" c"#; r"d""###);
+        let texts: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.text, token.kind))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("let", TokenKind::Ident),
+                ("r#type", TokenKind::Ident),
+                ("=", TokenKind::Punct),
+                (r##"r#"a "b" c"#"##, TokenKind::Literal),
+                (";", TokenKind::Punct),
+                (r#"r"d""#, TokenKind::Literal),
+            ]
+        );
+
+        // A `r#` at the end of the file isn't the start of anything.
+        let texts: Vec<_> = tokenize("x r#")
+            .iter()
+            .map(|token| (token.text, token.kind))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("x", TokenKind::Ident),
+                ("r", TokenKind::Ident),
+                ("#", TokenKind::Punct),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_shows_lifetime_changes() {
+        let exercise = "pub fn vector_set(vector: &mut Vec<&str>, new: &str) {\n    todo!()\n}\n";
+        let solution = "pub fn vector_set<'v, 'b>(\n    vector: &'v mut Vec<&'b str>,\n    new: &'b str,\n) {\n    vector[0] = new;\n}\n";
+
+        let file_diff = diff_file(exercise, solution, false, Style::Plain);
+        assert_eq!(file_diff.shown, 1);
+        assert_eq!(file_diff.hidden, 2);
+        assert_eq!(
+            file_diff.rendered,
+            "@@ line 1 @@\n\
+             - pub fn vector_set(vector: &mut Vec<&str>, new: &str) {\n\
+             + pub fn vector_set<'v, 'b>(\n\
+             \x20                  ^^^^ ^^^\n\
+             +     vector: &'v mut Vec<&'b str>,\n\
+             \x20              ^^          ^^\n\
+             +     new: &'b str,\n\
+             \x20           ^^\n"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Drills: randomly generated questions, answered at the terminal.
+//!
+//! Each session has a seed (printed at the start), so the same questions
+//! can be asked again with `--seed`.
+
+use std::io::{self, BufRead, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+
+use crate::elide;
+use crate::Result;
+
+/// What to drill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Topic {
+    /// Write out the lifetimes the compiler fills in for a signature.
+    Elision,
+}
+
+/// A small, seedable random number generator (SplitMix64), so sessions are
+/// the same on every platform.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+/// A seed based on the time, for when we aren't given one.
+pub fn random_seed() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_secs() ^ u64::from(now.subsec_nanos())
+}
+
+const FUNCTIONS: &[&str] = &[
+    "get", "find", "split", "first", "parse", "longest", "pick", "update",
+];
+const PARAMETERS: &[&str] = &["text", "key", "items", "config", "other", "count"];
+const REFERENTS: &[&str] = &["i32", "str", "[u8]", "String", "Vec<i32>"];
+/// What can go behind `&mut` (`&mut str` is legal, but confusing).
+const MUTABLE_REFERENTS: &[&str] = &["i32", "String", "Vec<i32>"];
+const OWNED: &[&str] = &["i32", "usize", "String", "bool"];
+/// Structs with a lifetime parameter (the drill's introduction says so).
+const STRUCTS: &[&str] = &["Words", "Parser"];
+
+/// A random type, which might contain references.
+fn random_type(rng: &mut Rng) -> String {
+    match rng.below(7) {
+        0 | 1 => format!("&{}", rng.pick(REFERENTS)),
+        2 => format!("&mut {}", rng.pick(MUTABLE_REFERENTS)),
+        3 => format!("Option<&{}>", rng.pick(REFERENTS)),
+        4 => match rng.below(2) {
+            0 => format!("(&{}, &{})", rng.pick(REFERENTS), rng.pick(REFERENTS)),
+            _ => format!("(&{}, usize)", rng.pick(REFERENTS)),
+        },
+        5 => format!("{}<'_>", rng.pick(STRUCTS)),
+        _ => rng.pick(OWNED).to_string(),
+    }
+}
+
+/// A random function signature, e.g. `fn get(&self, key: &str) -> &i32`.
+pub fn random_signature(rng: &mut Rng) -> String {
+    let mut parameters = vec![];
+    let receiver = rng.below(5);
+    match receiver {
+        0 => parameters.push("&self".to_string()),
+        1 => parameters.push("&mut self".to_string()),
+        2 => parameters.push("self".to_string()),
+        _ => {}
+    }
+    let count = if receiver < 3 {
+        rng.below(3)
+    } else {
+        rng.below(3) + 1
+    };
+    let mut names = PARAMETERS.to_vec();
+    for _ in 0..count {
+        let name = names.remove(rng.below(names.len()));
+        parameters.push(format!("{name}: {}", random_type(rng)));
+    }
+    let output = match rng.below(6) {
+        0 => String::new(),
+        _ => format!(" -> {}", random_type(rng)),
+    };
+    format!(
+        "fn {}({}){output}",
+        rng.pick(FUNCTIONS),
+        parameters.join(", ")
+    )
+}
+
+/// Asks `count` questions about `topic`, reading answers from `input`.
+/// Returns whether every answer was right.
+pub fn drill(input: &mut impl BufRead, topic: Topic, seed: u64, count: usize) -> Result<bool> {
+    let Topic::Elision = topic;
+    println!("Drilling the lifetime elision rules, with seed {seed}.\n");
+    println!(
+        "For each signature, write it out with every lifetime the compiler fills\n\
+         in (e.g. `fn f<'a>(x: &'a i32) -> &'a i32`), or write `elision fails`.\n\
+         The lifetimes can have any names. `Words<'a>` and `Parser<'a>` are\n\
+         structs with one lifetime parameter. Press Ctrl-D to stop."
+    );
+
+    let mut rng = Rng::new(seed);
+    let mut asked = 0;
+    let mut right = 0;
+    'questions: for number in 1..=count {
+        let question = random_signature(&mut rng);
+        println!("\nQuestion {number} of {count}:\n\n    {question}\n");
+        loop {
+            print!("> ");
+            io::stdout().flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                println!();
+                break 'questions;
+            }
+            if answer.trim().is_empty() {
+                continue;
+            }
+            match elide::check_answer(&question, &answer) {
+                Err(err) => {
+                    println!(
+                        "I couldn't read that ({err}).\n\
+                         Write out the whole signature, or `elision fails`."
+                    );
+                    continue;
+                }
+                Ok(None) => {
+                    right += 1;
+                    println!("✓ Right.");
+                }
+                Ok(Some(problem)) => println!("✗ Not quite: {problem}."),
+            }
+            println!("\n{}", elide::elide(&question)?);
+            asked += 1;
+            break;
+        }
+    }
+
+    println!(
+        "\nYou got {right} of {asked} right. To try these again, run \
+         `cargo run -- drill elision --seed {seed}`."
+    );
+    Ok(right == count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_repeat_questions() {
+        let questions = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..5)
+                .map(|_| random_signature(&mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(questions(7), questions(7));
+        assert_ne!(questions(7), questions(8));
+    }
+
+    #[test]
+    fn grades_generated_questions() {
+        let mut rng = Rng::new(0);
+        let (mut expanded, mut failed) = (0, 0);
+        for _ in 0..200 {
+            let question = random_signature(&mut rng);
+            let expansion = elide::elide(&question).unwrap();
+            let elision_fails = elide::check_answer(&question, "elision fails").unwrap();
+            if expansion.outcome.is_ok() {
+                expanded += 1;
+                assert!(elision_fails.is_some(), "{question}");
+                assert_eq!(
+                    elide::check_answer(&question, &expansion.expanded).unwrap(),
+                    None,
+                    "{question}"
+                );
+            } else {
+                failed += 1;
+                assert_eq!(elision_fails, None, "{question}");
+            }
+        }
+        assert!(expanded > 50 && failed > 20, "{expanded} {failed}");
+    }
+}
@@ -0,0 +1,760 @@
+//! Works out what lifetimes the compiler fills in for a function signature.
+//!
+//! This follows the elision rules from chapters 3 and 6:
+//!
+//!  1. Each elided lifetime in the parameters gets its own lifetime.
+//!  2. If there's exactly one input lifetime, it's assigned to every elided
+//!     output lifetime.
+//!  3. If one of the parameters is `&self` or `&mut self`, its lifetime is
+//!     assigned to every elided output lifetime.
+//!
+//! Lifetimes are elided wherever a reference has no lifetime (`&i32`), or
+//! where `'_` is written in a type's arguments (`Foo<'_>`) or in the bounds
+//! of a trait object or `impl Trait` (`Box<dyn Display + '_>`). We can't see
+//! type definitions, so a struct whose lifetime parameter is left out
+//! entirely (`Foo` rather than `Foo<'_>`) isn't noticed.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use proc_macro2::{LineColumn, Span};
+use syn::spanned::Spanned;
+
+use crate::Result;
+
+/// Where a lifetime appears (or would appear, if it weren't elided).
+#[derive(Debug, Clone)]
+struct Position {
+    /// Where it is: a parameter's name, `self`, or `the return value`.
+    place: String,
+    /// The lifetime written there, if any (`'_` counts as elided).
+    lifetime: Option<String>,
+    edit: Edit,
+}
+
+impl Position {
+    fn is_elided(&self) -> bool {
+        self.lifetime.is_none()
+    }
+}
+
+/// How to write a lifetime into the source at a position.
+#[derive(Debug, Clone, Copy)]
+enum Edit {
+    /// Insert `'a ` here (just after a `&`).
+    Insert(LineColumn),
+    /// Replace a `'_` between these points.
+    Replace(LineColumn, LineColumn),
+}
+
+/// Which rule gave the elided output lifetimes their lifetime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// There weren't any elided output lifetimes, so only rule 1 applied.
+    NoOutputs,
+    /// Rule 2: the only input lifetime.
+    OnlyInput(String),
+    /// Rule 3: the lifetime of `&self`.
+    SelfRef(String),
+}
+
+/// Why the elided output lifetimes couldn't be filled in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    /// There were no input lifetimes to choose from.
+    NoInputs,
+    /// There were several, and no `&self`.
+    Ambiguous(Vec<String>),
+}
+
+/// A signature, with its elided lifetimes filled in.
+#[derive(Debug, Clone)]
+pub struct Expansion {
+    /// The signature as it was given, with the lifetimes written out. If
+    /// elision failed, the output lifetimes are written as `'?`.
+    pub expanded: String,
+    /// The lifetimes rule 1 made up, with where each one is.
+    pub fresh: Vec<(String, String)>,
+    pub outcome: std::result::Result<Rule, Failure>,
+}
+
+impl fmt::Display for Expansion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}\n", self.expanded)?;
+        if self.fresh.is_empty() {
+            writeln!(f, "Rule 1: none of the input lifetimes were elided.")?;
+        } else {
+            let fresh: Vec<String> = self
+                .fresh
+                .iter()
+                .map(|(lifetime, place)| format!("`{lifetime}` for {place}"))
+                .collect();
+            writeln!(
+                f,
+                "Rule 1: each elided input lifetime gets its own lifetime: {}.",
+                fresh.join(", ")
+            )?;
+        }
+        match &self.outcome {
+            Ok(Rule::NoOutputs) => write!(f, "There are no elided output lifetimes to fill in."),
+            Ok(Rule::OnlyInput(lifetime)) => write!(
+                f,
+                "Rule 2: there's exactly one input lifetime, `{lifetime}`, so every \
+                 elided output lifetime is `{lifetime}`."
+            ),
+            Ok(Rule::SelfRef(lifetime)) => write!(
+                f,
+                "Rule 3: one of the parameters is a reference to `self`, so every \
+                 elided output lifetime is its lifetime, `{lifetime}`."
+            ),
+            Err(Failure::NoInputs) => write!(
+                f,
+                "Elision fails: there are no input lifetimes for the output to \
+                 borrow from. (Perhaps it should be `'static`, or an owned type?)"
+            ),
+            Err(Failure::Ambiguous(lifetimes)) => {
+                let lifetimes: Vec<String> = lifetimes
+                    .iter()
+                    .map(|lifetime| format!("`{lifetime}`"))
+                    .collect();
+                write!(
+                    f,
+                    "Elision fails: there are {} input lifetimes ({}), and no `&self`, \
+                     so the compiler can't tell which one the output borrows from. \
+                     You have to say which.",
+                    lifetimes.len(),
+                    lifetimes.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Parses a function signature (with or without `pub`, a trailing `;` or a
+/// body), and fills in its elided lifetimes.
+pub fn elide(source: &str) -> Result<Expansion> {
+    let (source, sig) = parse_signature(source)?;
+    Ok(expand(source, &sig))
+}
+
+/// Parses a function signature, returning it along with its source (minus
+/// any trailing `;`).
+fn parse_signature(source: &str) -> Result<(&str, syn::Signature)> {
+    let source = source.trim().trim_end_matches(';').trim_end();
+    let with_body = if source.ends_with('}') {
+        source.to_string()
+    } else {
+        format!("{source} {{}}")
+    };
+    match syn::parse_str::<syn::Item>(&with_body) {
+        Ok(syn::Item::Fn(function)) => Ok((source, function.sig)),
+        _ => Err(format!("couldn't parse `{source}` as a function signature").into()),
+    }
+}
+
+/// Checks an answer to "what lifetimes does the compiler give `question`?".
+///
+/// The answer is either the signature with every lifetime written out, or
+/// `elision fails`. Returns what's wrong with it, if anything.
+pub fn check_answer(question: &str, answer: &str) -> Result<Option<String>> {
+    let expected = fill(&parse_signature(question)?.1);
+    if answer
+        .trim()
+        .trim_end_matches('.')
+        .eq_ignore_ascii_case("elision fails")
+    {
+        return Ok(match expected.outcome {
+            Err(_) => None,
+            Ok(_) => Some("elision doesn't fail here".to_string()),
+        });
+    }
+
+    let actual = fill(&parse_signature(answer)?.1);
+    let unwritten = match (actual.fresh.first(), &actual.outcome) {
+        (Some((_, place)), _) => Some(place.clone()),
+        (None, Ok(Rule::NoOutputs)) => None,
+        (None, _) => Some("the return value".to_string()),
+    };
+    if let Some(place) = unwritten {
+        return Ok(Some(format!(
+            "write out every lifetime: one of the lifetimes in {place} is elided"
+        )));
+    }
+    if expected.outcome.is_err() {
+        return Ok(Some(
+            "elision fails here, so the compiler can't give the return value a lifetime"
+                .to_string(),
+        ));
+    }
+    Ok(compare(&expected.positions, &actual.positions))
+}
+
+/// Fills in the elided lifetimes of a signature parsed from `source`.
+pub fn expand(source: &str, sig: &syn::Signature) -> Expansion {
+    let filled = fill(sig);
+    let fresh_names: Vec<&String> = filled.fresh.iter().map(|(lifetime, _)| lifetime).collect();
+    let expanded = apply(source, sig, &fresh_names, filled.edits);
+    Expansion {
+        expanded,
+        fresh: filled.fresh,
+        outcome: filled.outcome,
+    }
+}
+
+/// A signature's lifetime positions, after the elision rules have been
+/// applied.
+struct Filled {
+    /// Every position, parameters first, with its lifetime filled in
+    /// (`'?` where elision failed).
+    positions: Vec<Position>,
+    fresh: Vec<(String, String)>,
+    /// The lifetimes to write into the source to make them explicit.
+    edits: Vec<(Edit, String)>,
+    outcome: std::result::Result<Rule, Failure>,
+}
+
+fn fill(sig: &syn::Signature) -> Filled {
+    let (mut inputs, self_lifetime) = input_positions(sig);
+    let mut outputs = vec![];
+    if let syn::ReturnType::Type(_, ty) = &sig.output {
+        type_positions(ty, "the return value", &mut outputs);
+    }
+
+    // Rule 1.
+    let mut used: BTreeSet<String> = sig
+        .generics
+        .lifetimes()
+        .map(|param| param.lifetime.to_string())
+        .collect();
+    used.extend(
+        inputs
+            .iter()
+            .chain(&outputs)
+            .filter_map(|position| position.lifetime.clone()),
+    );
+    let mut fresh = vec![];
+    let mut edits = vec![];
+    for position in &mut inputs {
+        if position.is_elided() {
+            let lifetime = fresh_lifetime(&used);
+            used.insert(lifetime.clone());
+            fresh.push((lifetime.clone(), position.place.clone()));
+            edits.push((position.edit, lifetime.clone()));
+            position.lifetime = Some(lifetime);
+        }
+    }
+    let self_lifetime = self_lifetime.map(|index| inputs[index].lifetime.clone().unwrap());
+
+    // Rules 2 and 3.
+    let input_lifetimes: Vec<String> = inputs
+        .iter()
+        .filter_map(|position| position.lifetime.clone())
+        .fold(vec![], |mut lifetimes, lifetime| {
+            if !lifetimes.contains(&lifetime) {
+                lifetimes.push(lifetime);
+            }
+            lifetimes
+        });
+    let outcome = if !outputs.iter().any(Position::is_elided) {
+        Ok(Rule::NoOutputs)
+    } else if let Some(lifetime) = self_lifetime {
+        Ok(Rule::SelfRef(lifetime))
+    } else if input_lifetimes.len() == 1 {
+        Ok(Rule::OnlyInput(input_lifetimes[0].clone()))
+    } else if input_lifetimes.is_empty() {
+        Err(Failure::NoInputs)
+    } else {
+        Err(Failure::Ambiguous(input_lifetimes))
+    };
+    let output_lifetime = match &outcome {
+        Ok(Rule::OnlyInput(lifetime) | Rule::SelfRef(lifetime)) => lifetime.clone(),
+        _ => "'?".to_string(),
+    };
+    for position in &mut outputs {
+        if position.is_elided() {
+            edits.push((position.edit, output_lifetime.clone()));
+            position.lifetime = Some(output_lifetime.clone());
+        }
+    }
+
+    inputs.extend(outputs);
+    Filled {
+        positions: inputs,
+        fresh,
+        edits,
+        outcome,
+    }
+}
+
+/// How one function's lifetimes compare to the ones the compiler would give
+/// the original.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grade {
+    pub function: String,
+    /// What's wrong, if anything.
+    pub problem: Option<String>,
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            None => write!(f, "✓ {}", self.function),
+            Some(problem) => write!(f, "✗ {}: {problem}", self.function),
+        }
+    }
+}
+
+/// Checks that every function in `attempt` has the lifetimes the compiler
+/// would give the function of the same name in `expected`. Usually
+/// `expected` is a solution with every lifetime written out, but any
+/// lifetimes it leaves out are filled in by the elision rules.
+///
+/// The names of the lifetimes don't matter, only which positions share one:
+/// `fn f<'x>(a: &'x i32) -> &'x i32` is as good as `fn f<'a>(a: &'a i32) ->
+/// &'a i32`.
+pub fn grade(expected: &str, attempt: &str) -> Result<Vec<Grade>> {
+    let expected = signatures(expected)?;
+    let attempt = signatures(attempt)?;
+    Ok(expected
+        .iter()
+        .map(|expected| {
+            let name = expected.ident.to_string();
+            let problem = match attempt.iter().find(|sig| sig.ident == name) {
+                None => Some("it's missing".to_string()),
+                Some(actual) => compare(&fill(expected).positions, &fill(actual).positions),
+            };
+            Grade {
+                function: name,
+                problem,
+            }
+        })
+        .collect())
+}
+
+/// Every function signature in a file (including those in `impl`s).
+fn signatures(source: &str) -> Result<Vec<syn::Signature>> {
+    let file = syn::parse_file(source)?;
+    let mut signatures = vec![];
+    for item in file.items {
+        match item {
+            syn::Item::Fn(function) => signatures.push(function.sig),
+            syn::Item::Impl(block) => {
+                for item in block.items {
+                    if let syn::ImplItem::Fn(function) = item {
+                        signatures.push(function.sig);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(signatures)
+}
+
+/// Explains the first position where `actual`'s lifetimes can't be renamed
+/// to match `expected`'s.
+fn compare(expected: &[Position], actual: &[Position]) -> Option<String> {
+    if expected.len() != actual.len() {
+        return Some(
+            "its types don't have as many lifetimes as the original's \
+             (only add lifetimes; don't change the types)"
+                .to_string(),
+        );
+    }
+    // Which lifetime in `actual` each lifetime in `expected` was renamed to,
+    // and the first position it was at.
+    let mut renamed: Vec<(&str, &str, usize)> = vec![];
+    for (index, (want, got)) in expected.iter().zip(actual).enumerate() {
+        let place = describe(actual, index);
+        let want = want.lifetime.as_deref().unwrap_or("'?");
+        let got = got.lifetime.as_deref().unwrap_or("'?");
+        if want == "'?" {
+            // Elision fails in the original, so any lifetime will do.
+            continue;
+        }
+        if got == "'?" {
+            return Some(format!("the lifetime of {place} can't be elided"));
+        }
+        if (want == "'static") != (got == "'static") {
+            return Some(if want == "'static" {
+                format!("{place} should be `'static`")
+            } else {
+                format!("{place} shouldn't be `'static`")
+            });
+        }
+        if let Some(&(_, other, first)) = renamed.iter().find(|(from, _, _)| *from == want) {
+            if other != got {
+                return Some(format!(
+                    "{place} should have the same lifetime as {}",
+                    describe(actual, first)
+                ));
+            }
+        } else if let Some(&(_, _, first)) = renamed.iter().find(|(_, to, _)| *to == got) {
+            return Some(format!(
+                "{place} should have a different lifetime from {}",
+                describe(actual, first)
+            ));
+        } else {
+            renamed.push((want, got, index));
+        }
+    }
+    None
+}
+
+/// Where a position is, saying which lifetime it is if there are several in
+/// the same place (like `&Option<&i32>`).
+fn describe(positions: &[Position], index: usize) -> String {
+    let place = &positions[index].place;
+    let same_place: Vec<usize> = (0..positions.len())
+        .filter(|&other| positions[other].place == *place)
+        .collect();
+    if same_place.len() == 1 {
+        return place.clone();
+    }
+    let nth = same_place.iter().position(|&other| other == index).unwrap() + 1;
+    let ordinal = match nth {
+        1 => "1st".to_string(),
+        2 => "2nd".to_string(),
+        3 => "3rd".to_string(),
+        n => format!("{n}th"),
+    };
+    format!("the {ordinal} lifetime in {place}")
+}
+
+/// The lifetime positions in a signature's parameters, and the index of the
+/// one belonging to `&self` (if there is one).
+fn input_positions(sig: &syn::Signature) -> (Vec<Position>, Option<usize>) {
+    let mut positions = vec![];
+    let mut self_lifetime = None;
+    for input in &sig.inputs {
+        match input {
+            syn::FnArg::Receiver(receiver) => match (&receiver.reference, &receiver.colon_token) {
+                (Some((and, lifetime)), None) => {
+                    self_lifetime = Some(positions.len());
+                    positions.push(lifetime_position("`self`", lifetime.as_ref(), and.span));
+                }
+                (_, Some(_)) => {
+                    if let syn::Type::Reference(reference) = &*receiver.ty {
+                        if matches!(&*reference.elem, syn::Type::Path(path) if path.path.is_ident("Self"))
+                        {
+                            self_lifetime = Some(positions.len());
+                        }
+                    }
+                    type_positions(&receiver.ty, "`self`", &mut positions);
+                }
+                (None, None) => {}
+            },
+            syn::FnArg::Typed(typed) => {
+                let place = match &*typed.pat {
+                    syn::Pat::Ident(ident) => format!("`{}`", ident.ident),
+                    pat => format!("`{}`", span_text(pat.span())),
+                };
+                type_positions(&typed.ty, &place, &mut positions);
+            }
+        }
+    }
+    (positions, self_lifetime)
+}
+
+/// Finds every lifetime position in a type.
+///
+/// Function pointers and `Fn` traits have their own elision rules, so we
+/// don't look inside them.
+fn type_positions(ty: &syn::Type, place: &str, positions: &mut Vec<Position>) {
+    match ty {
+        syn::Type::Reference(reference) => {
+            positions.push(lifetime_position(
+                place,
+                reference.lifetime.as_ref(),
+                reference.and_token.span,
+            ));
+            type_positions(&reference.elem, place, positions);
+        }
+        syn::Type::Path(path) => {
+            if let Some(qself) = &path.qself {
+                type_positions(&qself.ty, place, positions);
+            }
+            path_positions(&path.path, place, positions);
+        }
+        syn::Type::TraitObject(object) => bound_positions(&object.bounds, place, positions),
+        syn::Type::ImplTrait(imp) => bound_positions(&imp.bounds, place, positions),
+        syn::Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                type_positions(elem, place, positions);
+            }
+        }
+        syn::Type::Slice(slice) => type_positions(&slice.elem, place, positions),
+        syn::Type::Array(array) => type_positions(&array.elem, place, positions),
+        syn::Type::Paren(paren) => type_positions(&paren.elem, place, positions),
+        syn::Type::Group(group) => type_positions(&group.elem, place, positions),
+        _ => {}
+    }
+}
+
+/// Adds the lifetimes in a path's generic arguments (like `Foo<'_, &i32>`).
+fn path_positions(path: &syn::Path, place: &str, positions: &mut Vec<Position>) {
+    for segment in &path.segments {
+        if let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments {
+            for argument in &arguments.args {
+                match argument {
+                    syn::GenericArgument::Lifetime(lifetime) => {
+                        positions.push(lifetime_position(place, Some(lifetime), lifetime.span()));
+                    }
+                    syn::GenericArgument::Type(ty) => type_positions(ty, place, positions),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Adds the lifetimes in the bounds of a `dyn Trait` or `impl Trait` (like
+/// the `'_` in `dyn Display + '_`).
+fn bound_positions<P>(
+    bounds: &syn::punctuated::Punctuated<syn::TypeParamBound, P>,
+    place: &str,
+    positions: &mut Vec<Position>,
+) {
+    for bound in bounds {
+        match bound {
+            syn::TypeParamBound::Lifetime(lifetime) => {
+                positions.push(lifetime_position(place, Some(lifetime), lifetime.span()));
+            }
+            syn::TypeParamBound::Trait(bound) => path_positions(&bound.path, place, positions),
+            _ => {}
+        }
+    }
+}
+
+/// A position with the given lifetime. If there isn't one, the lifetime
+/// would go just after `span` (a `&`).
+fn lifetime_position(place: &str, lifetime: Option<&syn::Lifetime>, span: Span) -> Position {
+    match lifetime {
+        Some(lifetime) if lifetime.ident == "_" => Position {
+            place: place.to_string(),
+            lifetime: None,
+            edit: Edit::Replace(lifetime.span().start(), lifetime.span().end()),
+        },
+        Some(lifetime) => Position {
+            place: place.to_string(),
+            lifetime: Some(lifetime.to_string()),
+            edit: Edit::Insert(span.end()),
+        },
+        None => Position {
+            place: place.to_string(),
+            lifetime: None,
+            edit: Edit::Insert(span.end()),
+        },
+    }
+}
+
+/// The first of `'a`, `'b`, ..., `'z`, `'a1`, ... which isn't used.
+fn fresh_lifetime(used: &BTreeSet<String>) -> String {
+    (0..)
+        .flat_map(|round| {
+            ('a'..='z').map(move |letter| match round {
+                0 => format!("'{letter}"),
+                _ => format!("'{letter}{round}"),
+            })
+        })
+        .find(|lifetime| !used.contains(lifetime))
+        .unwrap()
+}
+
+fn span_text(span: Span) -> String {
+    span.source_text().unwrap_or_default()
+}
+
+/// Writes the given lifetimes into `source`, declaring `fresh` in the
+/// signature's generics.
+fn apply(
+    source: &str,
+    sig: &syn::Signature,
+    fresh: &[&String],
+    edits: Vec<(Edit, String)>,
+) -> String {
+    let mut insertions: Vec<(usize, usize, String)> = edits
+        .into_iter()
+        .map(|(edit, lifetime)| match edit {
+            Edit::Insert(at) => {
+                let at = offset(source, at);
+                (at, at, format!("{lifetime} "))
+            }
+            Edit::Replace(start, end) => (offset(source, start), offset(source, end), lifetime),
+        })
+        .collect();
+
+    if !fresh.is_empty() {
+        let names: Vec<&str> = fresh.iter().map(|name| name.as_str()).collect();
+        let names = names.join(", ");
+        let generics = &sig.generics;
+        let insertion = match (&generics.lt_token, generics.lifetimes().last()) {
+            (None, _) => {
+                let at = offset(source, sig.ident.span().end());
+                (at, at, format!("<{names}>"))
+            }
+            (Some(_), Some(last)) => {
+                let at = offset(source, last.span().end());
+                (at, at, format!(", {names}"))
+            }
+            (Some(lt), None) => {
+                let at = offset(source, lt.span.end());
+                let separator = if generics.params.is_empty() { "" } else { ", " };
+                (at, at, format!("{names}{separator}"))
+            }
+        };
+        insertions.push(insertion);
+    }
+
+    insertions.sort_by_key(|(start, _, _)| *start);
+    let mut expanded = source.to_string();
+    for (start, end, text) in insertions.into_iter().rev() {
+        expanded.replace_range(start..end, &text);
+    }
+    expanded
+}
+
+/// The byte offset of a line and (character) column in `source`.
+fn offset(source: &str, at: LineColumn) -> usize {
+    let line_start: usize = source
+        .split_inclusive('\n')
+        .take(at.line - 1)
+        .map(str::len)
+        .sum();
+    let line = &source[line_start..];
+    line_start
+        + line
+            .char_indices()
+            .nth(at.column)
+            .map_or(line.len(), |(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+
+    fn expanded(signature: &str) -> String {
+        elide(signature).unwrap().expanded
+    }
+
+    #[test]
+    fn expands_like_the_ex03_solutions() {
+        assert_eq!(
+            expanded("fn example_a(_number: &i32) -> (&i32, &i32)"),
+            "fn example_a<'a>(_number: &'a i32) -> (&'a i32, &'a i32)"
+        );
+        assert_eq!(
+            expanded(
+                "pub fn example_b(_first_arg: &i32, _second_arg: &i32, _third_arg: &Option<&i32>)"
+            ),
+            "pub fn example_b<'a, 'b, 'c, 'd>(_first_arg: &'a i32, _second_arg: &'b i32, \
+             _third_arg: &'c Option<&'d i32>)"
+        );
+        assert_eq!(
+            expanded("fn example_c<'a>(_first_arg: &'a i32, _second_arg: &'a i32) -> &i32"),
+            "fn example_c<'a>(_first_arg: &'a i32, _second_arg: &'a i32) -> &'a i32"
+        );
+        assert_eq!(
+            expanded("fn example_d<'a>(_first_arg: &'a i32, _second_arg: &i32) -> &'a i32;"),
+            "fn example_d<'a, 'b>(_first_arg: &'a i32, _second_arg: &'b i32) -> &'a i32"
+        );
+    }
+
+    #[test]
+    fn applies_each_rule() {
+        let expansion = elide("fn f(a: &i32, b: &Option<&i32>) -> &i32").unwrap();
+        assert_eq!(
+            expansion.outcome,
+            Err(Failure::Ambiguous(vec![
+                "'a".to_string(),
+                "'b".to_string(),
+                "'c".to_string()
+            ]))
+        );
+        assert_eq!(
+            expansion.expanded,
+            "fn f<'a, 'b, 'c>(a: &'a i32, b: &'b Option<&'c i32>) -> &'? i32"
+        );
+
+        let expansion = elide("fn first<T>(items: &[T]) -> Option<&T>").unwrap();
+        assert_eq!(expansion.outcome, Ok(Rule::OnlyInput("'a".to_string())));
+        assert_eq!(
+            expansion.expanded,
+            "fn first<'a, T>(items: &'a [T]) -> Option<&'a T>"
+        );
+
+        let expansion = elide("fn next_word(&mut self, skip: &str) -> Option<&str>").unwrap();
+        assert_eq!(expansion.outcome, Ok(Rule::SelfRef("'a".to_string())));
+        assert_eq!(
+            expansion.expanded,
+            "fn next_word<'a, 'b>(&'a mut self, skip: &'b str) -> Option<&'a str>"
+        );
+
+        let expansion = elide("fn new(text: &str) -> Words<'_>").unwrap();
+        assert_eq!(expansion.expanded, "fn new<'a>(text: &'a str) -> Words<'a>");
+
+        let expansion = elide("fn f(x: Box<dyn Tr + '_>) -> &str").unwrap();
+        assert_eq!(expansion.outcome, Ok(Rule::OnlyInput("'a".to_string())));
+        assert_eq!(
+            expansion.expanded,
+            "fn f<'a>(x: Box<dyn Tr + 'a>) -> &'a str"
+        );
+
+        let expansion = elide("fn show(text: &str) -> impl Display + '_").unwrap();
+        assert_eq!(
+            expansion.expanded,
+            "fn show<'a>(text: &'a str) -> impl Display + 'a"
+        );
+
+        let expansion = elide("fn make() -> &str").unwrap();
+        assert_eq!(expansion.outcome, Err(Failure::NoInputs));
+    }
+
+    #[test]
+    fn grades_ex03() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("exercises/03_lifetime_elision");
+        let original = fs::read_to_string(dir.join("exercise/src/lib.rs")).unwrap();
+        let solution = fs::read_to_string(dir.join("solutions/src/lib.rs")).unwrap();
+        let grades = grade(&original, &solution).unwrap();
+        assert_eq!(grades.len(), 4);
+        assert!(
+            grades.iter().all(|grade| grade.problem.is_none()),
+            "{grades:?}"
+        );
+
+        let problem = |attempt: &str| grade(&original, attempt).unwrap()[0].problem.clone();
+        let renamed = "fn example_a<'x>(_number: &'x i32) -> (&'x i32, &'x i32) {}";
+        assert_eq!(problem(renamed), None);
+        let split = "fn example_a<'a, 'b>(_number: &'a i32) -> (&'a i32, &'b i32) {}";
+        assert_eq!(
+            problem(split).unwrap(),
+            "the 2nd lifetime in the return value should have the same lifetime as `_number`"
+        );
+
+        let shared = "fn example_b<'a>(_first_arg: &'a i32, _second_arg: &'a i32, \
+                      _third_arg: &'a Option<&'a i32>) {}";
+        assert_eq!(
+            grade(&original, shared).unwrap()[1]
+                .problem
+                .as_deref()
+                .unwrap(),
+            "`_second_arg` should have a different lifetime from `_first_arg`"
+        );
+        let swapped =
+            "fn example_d<'a, 'b>(_first_arg: &'a i32, _second_arg: &'b i32) -> &'b i32 {}";
+        assert_eq!(
+            grade(&original, swapped).unwrap()[3]
+                .problem
+                .as_deref()
+                .unwrap(),
+            "the return value should have the same lifetime as `_first_arg`"
+        );
+        assert_eq!(
+            grade(&original, "").unwrap()[2].problem.as_deref().unwrap(),
+            "it's missing"
+        );
+    }
+}
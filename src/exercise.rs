@@ -0,0 +1,344 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::diagnostics::ErrorKata;
+use crate::Result;
+
+/// Whether an exercise crate is a library or a binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Lib,
+    Bin,
+}
+
+impl Kind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Lib => "lib",
+            Kind::Bin => "bin",
+        }
+    }
+}
+
+/// Which of an exercise's two crates we mean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    /// The crate the learner works on.
+    Exercise,
+    /// The reference solution.
+    Solution,
+}
+
+/// How we check that an exercise works, once it builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Check {
+    /// Run its tests (doc-tests count for libraries).
+    Test,
+    /// Run it, and check it exits successfully.
+    Run,
+}
+
+/// What happens when an exercise is checked before the learner solves it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Unsolved {
+    /// Most exercises are missing lifetimes, so can't compile.
+    #[default]
+    FailsToBuild,
+    /// The exercise compiles, but its tests fail.
+    FailsTests,
+    /// The exercise already works (e.g. it's about simplifying lifetimes).
+    Passes,
+}
+
+/// One kata: a chapter directory with an `exercise` and a `solutions` crate.
+///
+/// These are read from `exercises/manifest.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Exercise {
+    /// The chapter number, e.g. `4` for `04_mutable_references_and_containers`.
+    pub number: u32,
+    /// The chapter's title, e.g. "Mutable References and Containers".
+    pub title: String,
+    /// The chapter's page in the book, e.g. `chapter_4.md`.
+    pub chapter: String,
+    /// The chapter directory, e.g. `exercises/04_mutable_references_and_containers`.
+    pub dir: PathBuf,
+    /// The package name of the exercise crate, e.g. `ex04`.
+    pub package: String,
+    /// The package name of the solution crate, e.g. `soln04`.
+    pub solution_package: String,
+    pub kind: Kind,
+    pub check: Check,
+    #[serde(default)]
+    pub unsolved: Unsolved,
+    /// Hints, in the order they're revealed; each gives away more than the last.
+    pub hints: Vec<String>,
+    /// Shown once the exercise passes.
+    #[serde(default)]
+    pub explanation: Option<String>,
+    /// Optional exercises are never suggested as the next one to do.
+    #[serde(default)]
+    pub optional: bool,
+    /// Whether to check that every function has the lifetimes the compiler
+    /// would elide (see [`crate::elide::grade`]).
+    #[serde(default)]
+    pub grade_elision: bool,
+    /// Files which should fail to compile with particular errors.
+    #[serde(default)]
+    pub error_katas: Vec<ErrorKata>,
+}
+
+impl Exercise {
+    /// A library exercise with just a number (and the package names that go
+    /// with it), for tests to fill in as they need.
+    #[cfg(test)]
+    pub fn for_test(number: u32) -> Exercise {
+        Exercise {
+            number,
+            title: String::new(),
+            chapter: String::new(),
+            dir: PathBuf::new(),
+            package: format!("ex{number:02}"),
+            solution_package: format!("soln{number:02}"),
+            kind: Kind::Lib,
+            check: Check::Test,
+            unsolved: Unsolved::FailsToBuild,
+            hints: vec![],
+            explanation: None,
+            optional: false,
+            grade_elision: false,
+            error_katas: vec![],
+        }
+    }
+
+    pub fn package_for(&self, version: Version) -> &str {
+        match version {
+            Version::Exercise => &self.package,
+            Version::Solution => &self.solution_package,
+        }
+    }
+
+    /// The directory of one of the crates, relative to the root.
+    pub fn crate_dir(&self, version: Version) -> PathBuf {
+        match version {
+            Version::Exercise => self.dir.join("exercise"),
+            Version::Solution => self.dir.join("solutions"),
+        }
+    }
+
+    /// The crate's root source file, relative to the crate.
+    pub fn source_file(&self) -> &'static str {
+        match self.kind {
+            Kind::Lib => "src/lib.rs",
+            Kind::Bin => "src/main.rs",
+        }
+    }
+
+    /// The snapshot of the errors the unsolved exercise produces, relative
+    /// to the root (see [`crate::diagnostics::snapshot`]).
+    pub fn snapshot_path(&self) -> PathBuf {
+        self.dir.join("unsolved.errors")
+    }
+
+    /// The directories (relative to the crate) which the learner edits:
+    /// `src`, and wherever the error katas are.
+    pub fn source_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![PathBuf::from("src")];
+        for kata in &self.error_katas {
+            let dir = kata.file.parent().unwrap_or(Path::new("")).to_path_buf();
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        dirs
+    }
+}
+
+/// Finds the root of the lifetimekata checkout.
+///
+/// This is the first directory (starting from the current one) that contains
+/// both a `Cargo.toml` and an `exercises` directory. If there isn't one, we
+/// fall back to the directory this binary was built from.
+pub fn find_root() -> Result<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    for dir in cwd.ancestors() {
+        if is_root(dir) {
+            return Ok(dir.to_path_buf());
+        }
+    }
+
+    let built_from = Path::new(env!("CARGO_MANIFEST_DIR"));
+    if is_root(built_from) {
+        return Ok(built_from.to_path_buf());
+    }
+
+    Err("could not find the lifetimekata directory; run this from inside your checkout".into())
+}
+
+fn is_root(dir: &Path) -> bool {
+    dir.join("Cargo.toml").is_file() && dir.join("exercises").is_dir()
+}
+
+/// The manifest listing every exercise, relative to the root.
+pub const MANIFEST: &str = "exercises/manifest.toml";
+
+/// Everything in the manifest.
+#[derive(Deserialize)]
+struct Manifest {
+    kata: Vec<Exercise>,
+}
+
+/// Reads every exercise from the manifest, sorted by number.
+pub fn discover(root: &Path) -> Result<Vec<Exercise>> {
+    let path = root.join(MANIFEST);
+    let manifest: Manifest = toml::from_str(&fs::read_to_string(&path)?)
+        .map_err(|err| format!("couldn't read {}: {err}", path.display()))?;
+    let mut exercises = manifest.kata;
+    exercises.sort_by_key(|exercise| exercise.number);
+    Ok(exercises)
+}
+
+/// The width of the longest package name, for lining up tables.
+pub fn package_width(exercises: &[Exercise]) -> usize {
+    exercises
+        .iter()
+        .map(|exercise| exercise.package.len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Looks up an exercise by its chapter number.
+pub fn find(exercises: &[Exercise], number: u32) -> Result<&Exercise> {
+    exercises
+        .iter()
+        .find(|exercise| exercise.number == number)
+        .ok_or_else(|| {
+            let known: Vec<String> = exercises.iter().map(|e| e.number.to_string()).collect();
+            format!(
+                "there is no exercise {number} (try one of {})",
+                known.join(", ")
+            )
+            .into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::process::Command;
+
+    #[test]
+    fn discovers_workspace_exercises() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let exercises = discover(root).unwrap();
+
+        let ex04 = find(&exercises, 4).unwrap();
+        assert!(find(&exercises, 3).unwrap().grade_elision);
+        assert_eq!(ex04.package, "ex04");
+        assert_eq!(ex04.kind, Kind::Lib);
+        assert_eq!(ex04.error_katas.len(), 1);
+
+        let ex07 = find(&exercises, 7).unwrap();
+        assert_eq!(ex07.kind, Kind::Bin);
+        assert_eq!(ex07.check, Check::Run);
+        assert_eq!(ex07.unsolved, Unsolved::Passes);
+
+        let ex08 = find(&exercises, 8).unwrap();
+        assert_eq!(ex08.kind, Kind::Bin);
+        assert_eq!(ex08.check, Check::Test);
+
+        assert!(find(&exercises, 99).is_err());
+    }
+
+    /// Checks the manifest against the workspace and the book, so a kata
+    /// can't be half added.
+    #[test]
+    fn manifest_matches_workspace() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let exercises = discover(root).unwrap();
+
+        let output = Command::new(crate::runner::cargo_path())
+            .args(["metadata", "--no-deps", "--format-version", "1"])
+            .current_dir(root)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "cargo metadata failed");
+        let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let root = Path::new(metadata["workspace_root"].as_str().unwrap());
+        // Every package in the workspace (except the runner), by name.
+        let mut packages: BTreeMap<String, PathBuf> = metadata["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|package| {
+                let manifest = Path::new(package["manifest_path"].as_str().unwrap());
+                (
+                    package["name"].as_str().unwrap().to_string(),
+                    manifest.parent().unwrap().to_path_buf(),
+                )
+            })
+            .filter(|(name, _)| name != env!("CARGO_PKG_NAME"))
+            .collect();
+
+        let summary = fs::read_to_string(root.join("book/src/SUMMARY.md")).unwrap();
+        let mut numbers = vec![];
+        for exercise in &exercises {
+            for version in [Version::Exercise, Version::Solution] {
+                let package = exercise.package_for(version);
+                let dir = packages.remove(package).unwrap_or_else(|| {
+                    panic!("{package} is in the manifest, but not the workspace")
+                });
+                assert_eq!(dir, root.join(exercise.crate_dir(version)), "{package}");
+
+                let (file, other) = match exercise.kind {
+                    Kind::Lib => ("lib.rs", "main.rs"),
+                    Kind::Bin => ("main.rs", "lib.rs"),
+                };
+                assert!(
+                    dir.join("src").join(file).is_file(),
+                    "{package} has no {file}"
+                );
+                assert!(
+                    !dir.join("src").join(other).exists(),
+                    "{package} has a {other}"
+                );
+            }
+            assert!(
+                !exercise.hints.is_empty(),
+                "{} has no hints",
+                exercise.package
+            );
+            for kata in &exercise.error_katas {
+                let file = root
+                    .join(exercise.crate_dir(Version::Exercise))
+                    .join(&kata.file);
+                assert!(file.is_file(), "{} doesn't exist", file.display());
+            }
+            assert!(
+                summary.contains(&format!("{}](./{})", exercise.title, exercise.chapter)),
+                "chapter {} isn't in the book's summary",
+                exercise.number
+            );
+            numbers.push(exercise.number);
+        }
+
+        assert!(
+            packages.is_empty(),
+            "these packages aren't in the manifest: {:?}",
+            packages.keys().collect::<Vec<_>>()
+        );
+        numbers.dedup();
+        assert_eq!(
+            numbers.len(),
+            exercises.len(),
+            "exercise numbers must be unique"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! The fun examples: small programs which don't compile, for surprising
+//! reasons.
+//!
+//! Each is listed in `exercises/fun_examples/examples.toml` with an
+//! explanation. Next to each file is a snapshot of the errors it should
+//! produce (`example.rs` has `example.errors`; see [`diagnostics::snapshot`]),
+//! so we notice if a new rustc stops failing in the way the explanation
+//! describes. Run with `LIFETIMEKATA_BLESS=1` to update the snapshots (see
+//! [`diagnostics::blessing`]).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::diagnostics;
+use crate::Result;
+
+/// The directory the examples are in, relative to the root.
+pub const DIR: &str = "exercises/fun_examples";
+
+/// One example, from `examples.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Example {
+    /// The file, relative to [`DIR`].
+    pub file: PathBuf,
+    pub title: String,
+    /// Why it doesn't compile.
+    pub explanation: String,
+}
+
+impl Example {
+    /// The name used on the command line: the file name, without `.rs`.
+    pub fn name(&self) -> String {
+        self.file
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn snapshot_path(&self, root: &Path) -> PathBuf {
+        root.join(DIR).join(&self.file).with_extension("errors")
+    }
+}
+
+#[derive(Deserialize)]
+struct ExampleList {
+    example: Vec<Example>,
+}
+
+/// Reads every example from `examples.toml`.
+pub fn load(root: &Path) -> Result<Vec<Example>> {
+    let path = root.join(DIR).join("examples.toml");
+    let list: ExampleList = toml::from_str(&fs::read_to_string(&path)?)
+        .map_err(|err| format!("couldn't read {}: {err}", path.display()))?;
+    Ok(list.example)
+}
+
+/// The result of compiling an example.
+pub struct Outcome {
+    /// The errors it produced, summarised by [`diagnostics::snapshot`].
+    pub actual: String,
+    /// The errors it should have produced.
+    pub expected: String,
+    /// rustc's rendering of the errors.
+    pub rendered: String,
+}
+
+impl Outcome {
+    pub fn matches(&self) -> bool {
+        self.actual == self.expected
+    }
+}
+
+/// Compiles an example, and compares its errors to its snapshot.
+///
+/// If `LIFETIMEKATA_BLESS` is set, the snapshot is updated to match first.
+pub fn check(root: &Path, example: &Example) -> Result<Outcome> {
+    let file = root.join(DIR).join(&example.file);
+    let diagnostics = diagnostics::compile_file(&file)?;
+    let actual = diagnostics::snapshot(root, &diagnostics)?;
+
+    let snapshot_path = example.snapshot_path(root);
+    if diagnostics::blessing() {
+        fs::write(&snapshot_path, &actual)?;
+    }
+    let expected = fs::read_to_string(&snapshot_path).unwrap_or_default();
+    let rendered = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+        .filter_map(|diagnostic| diagnostic.rendered.as_deref())
+        .collect();
+    Ok(Outcome {
+        actual,
+        expected,
+        rendered,
+    })
+}
+
+/// Lists the examples, or explains one of them.
+pub fn explore(root: &Path, name: Option<&str>) -> Result<bool> {
+    let examples = load(root)?;
+    let Some(name) = name else {
+        let width = examples.iter().map(|e| e.name().len()).max().unwrap_or(0);
+        for example in &examples {
+            println!("{:<width$}  {}", example.name(), example.title);
+        }
+        println!("\nTo see why one doesn't compile, run `cargo run -- explore <name>`.");
+        return Ok(true);
+    };
+
+    let example = examples
+        .iter()
+        .find(|example| example.name() == name)
+        .ok_or_else(|| {
+            let known: Vec<String> = examples.iter().map(Example::name).collect();
+            format!(
+                "there is no example called {name} (try one of {})",
+                known.join(", ")
+            )
+        })?;
+
+    let file = Path::new(DIR).join(&example.file);
+    println!("==> {}: {}\n", file.display(), example.title);
+    print!("{}", fs::read_to_string(root.join(&file))?);
+    println!("\n==> rustc {}\n", file.display());
+    let outcome = check(root, example)?;
+    print!("{}", outcome.rendered);
+    println!("==> Why?\n\n{}", example.explanation);
+
+    if !outcome.matches() {
+        println!(
+            "\n(This version of rustc doesn't report the errors this explanation \
+             was written for. It reports:\n{}and the explanation expects:\n{})",
+            outcome.actual, outcome.expected
+        );
+    }
+    Ok(outcome.matches())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn examples_match_snapshots() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let examples = load(root).unwrap();
+        assert!(!examples.is_empty());
+        for example in &examples {
+            let outcome = check(root, example).unwrap();
+            assert!(!outcome.actual.is_empty(), "{} compiles", example.name());
+            assert_eq!(outcome.actual, outcome.expected, "{}", example.name());
+        }
+    }
+}
@@ -1,3 +1,248 @@
-fn main() {
-    println!("Hello, world!");
+//! The LifetimeKata runner.
+//!
+//! This finds the exercises in the `exercises` directory, and knows how to
+//! build, test and run each of them.
+
+mod diagnostics;
+mod diff;
+mod drill;
+mod elide;
+mod exercise;
+mod explore;
+mod progress;
+mod reset;
+mod runner;
+mod scopes;
+mod verify;
+mod watch;
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use crate::progress::Progress;
+use crate::runner::Verdict;
+
+pub type Result<T, E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+#[derive(Parser)]
+#[command(about = "Exercises to improve your understanding of lifetimes in Rust.")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List every exercise, and how it is checked.
+    List,
+    /// Build an exercise, then test it (or run it, if it has no tests).
+    Test {
+        /// The exercise number, e.g. `4` for `ex04`.
+        number: u32,
+    },
+    /// Build and run a binary exercise.
+    Run {
+        /// The exercise number, e.g. `7` for `ex07`.
+        number: u32,
+    },
+    /// Show how the lifetimes in an exercise differ from its solution.
+    Diff {
+        /// The exercise number, e.g. `2` for `ex02`.
+        number: u32,
+        /// Show every difference, not just the lifetimes.
+        #[arg(long)]
+        all: bool,
+    },
+    /// Reveal the next hint for an exercise (and the ones you've already seen).
+    Hint {
+        /// The exercise number, e.g. `4` for `ex04`.
+        number: u32,
+    },
+    /// Show which exercises you've passed.
+    Status,
+    /// Show the first exercise you haven't passed yet.
+    Next,
+    /// Check an exercise every time you save it.
+    Watch {
+        /// The exercise to watch. By default, this is the next one you
+        /// haven't passed, moving on to the one after when it passes.
+        number: Option<u32>,
+    },
+    /// Undo your changes to an exercise, keeping a backup of them.
+    Reset {
+        /// The exercise number, e.g. `8` for `ex08`.
+        number: u32,
+        /// Don't ask for confirmation.
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Write out the lifetimes the compiler fills in for a function signature,
+    /// e.g. `cargo run -- elide 'fn f(a: &i32) -> &i32'`.
+    Elide {
+        /// The signature, e.g. `fn f(a: &i32, b: &Option<&i32>) -> &i32`.
+        signature: String,
+    },
+    /// Answer randomly generated questions, e.g. `cargo run -- drill elision`.
+    Drill {
+        /// What to drill.
+        #[arg(value_enum)]
+        topic: drill::Topic,
+        /// Ask the same questions as a previous session.
+        #[arg(long)]
+        seed: Option<u64>,
+        /// How many questions to ask.
+        #[arg(long, default_value_t = 5)]
+        count: usize,
+    },
+    /// List the fun examples, or compile and explain one of them.
+    Explore {
+        /// The example to explain, e.g. `matched_lifetimes`.
+        name: Option<String>,
+    },
+    /// Draw the scope of every local variable and borrow in a Rust snippet.
+    Scopes {
+        /// The file the snippet is in (`-` for stdin).
+        file: PathBuf,
+        /// Draw an HTML figure, for the book, instead of text.
+        #[arg(long)]
+        html: bool,
+    },
+    /// Check that every solution passes, and every exercise fails as it
+    /// should before it's solved.
+    VerifySolutions,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs a command, returning whether it succeeded.
+fn run(command: Command) -> Result<bool> {
+    let root = exercise::find_root()?;
+    let exercises = exercise::discover(&root)?;
+    reset::record_originals(&root, &exercises)?;
+
+    match command {
+        Command::List => {
+            let width = exercise::package_width(&exercises);
+            for exercise in &exercises {
+                let step = runner::steps(exercise)[1];
+                let errors = if !exercise.error_katas.is_empty() {
+                    "+ errors"
+                } else if exercise.grade_elision {
+                    "+ graded"
+                } else {
+                    ""
+                };
+                println!(
+                    "{:>2}  {:<width$} {}  cargo {step:<5} {errors:<8}  {}",
+                    exercise.number,
+                    exercise.package,
+                    exercise.kind.as_str(),
+                    exercise.title,
+                );
+            }
+            Ok(true)
+        }
+        Command::Test { number } => {
+            let exercise = exercise::find(&exercises, number)?;
+            let verdict = runner::check(&root, exercise)?;
+            runner::print_verdict(exercise, verdict);
+
+            let mut progress = Progress::load(&root)?;
+            progress.record(exercise, verdict == Verdict::Passed);
+            progress.save(&root)?;
+
+            Ok(verdict == Verdict::Passed)
+        }
+        Command::Run { number } => {
+            let exercise = exercise::find(&exercises, number)?;
+            runner::run(&root, exercise)
+        }
+        Command::Diff { number, all } => {
+            let exercise = exercise::find(&exercises, number)?;
+            diff::diff(&root, exercise, all)?;
+            Ok(true)
+        }
+        Command::Hint { number } => {
+            let exercise = exercise::find(&exercises, number)?;
+            let mut progress = Progress::load(&root)?;
+            let seen = progress.get(exercise).hints_used;
+            let used = progress.use_hint(exercise);
+            progress.save(&root)?;
+
+            for (i, hint) in exercise.hints.iter().enumerate().take(used) {
+                println!("Hint {} of {}: {hint}\n", i + 1, exercise.hints.len());
+            }
+            if used == seen {
+                println!("That's every hint for {}.", exercise.package);
+            } else if used < exercise.hints.len() {
+                println!("Run this again for another hint.");
+            }
+            Ok(true)
+        }
+        Command::Status => {
+            Progress::load(&root)?.print_status(&exercises);
+            Ok(true)
+        }
+        Command::Next => {
+            match Progress::load(&root)?.next(&exercises) {
+                Some(exercise) => {
+                    println!(
+                        "Next up: {}, {} ({})",
+                        exercise.package,
+                        exercise.title,
+                        exercise.dir.display()
+                    );
+                    println!("The chapter is book/src/{}.", exercise.chapter);
+                    println!(
+                        "When you're ready, run `cargo run -- test {}`.",
+                        exercise.number
+                    );
+                }
+                None => println!("You've passed every exercise. Congratulations!"),
+            }
+            Ok(true)
+        }
+        Command::Watch { number } => watch::watch(&root, &exercises, number),
+        Command::Reset { number, yes } => {
+            let exercise = exercise::find(&exercises, number)?;
+            reset::reset(&root, exercise, yes)
+        }
+        Command::Elide { signature } => {
+            let expansion = elide::elide(&signature)?;
+            println!("{expansion}");
+            Ok(expansion.outcome.is_ok())
+        }
+        Command::Drill { topic, seed, count } => {
+            let seed = seed.unwrap_or_else(drill::random_seed);
+            drill::drill(&mut std::io::stdin().lock(), topic, seed, count)
+        }
+        Command::Explore { name } => explore::explore(&root, name.as_deref()),
+        Command::Scopes { file, html } => {
+            let source = if file == Path::new("-") {
+                std::io::read_to_string(std::io::stdin())?
+            } else {
+                std::fs::read_to_string(&file)?
+            };
+            let diagram = scopes::analyse(&source)?;
+            if html {
+                print!("{}", diagram.render_html());
+            } else {
+                print!("{}", diagram.render_text());
+            }
+            Ok(diagram.problems().is_empty())
+        }
+        Command::VerifySolutions => verify::verify_solutions(&root, &exercises),
+    }
 }
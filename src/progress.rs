@@ -0,0 +1,210 @@
+//! Keeps track of which exercises a learner has passed.
+//!
+//! This lives in `.lifetimekata/progress.toml`, in the root of the checkout.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::exercise::Exercise;
+use crate::Result;
+
+/// The directory the runner keeps its state in.
+pub fn state_dir(root: &Path) -> PathBuf {
+    root.join(".lifetimekata")
+}
+
+/// What we know about one exercise.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExerciseProgress {
+    /// How many times it was checked before it first passed (including
+    /// that passing check).
+    #[serde(default)]
+    pub attempts: u32,
+    /// When it first passed, in seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passed_at: Option<u64>,
+    /// How many of its hints have been revealed.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub hints_used: usize,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+/// Everything in the progress file, keyed by package name (`ex04`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Progress {
+    exercises: BTreeMap<String, ExerciseProgress>,
+}
+
+impl Progress {
+    fn path(root: &Path) -> PathBuf {
+        state_dir(root).join("progress.toml")
+    }
+
+    /// Loads the progress file, or starts afresh if there isn't one.
+    pub fn load(root: &Path) -> Result<Progress> {
+        let path = Progress::path(root);
+        match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text)
+                .map_err(|err| format!("{} is corrupted: {err}", path.display()).into()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Progress::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        fs::create_dir_all(state_dir(root))?;
+        fs::write(Progress::path(root), toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, exercise: &Exercise) -> ExerciseProgress {
+        self.exercises
+            .get(&exercise.package)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn passed(&self, exercise: &Exercise) -> bool {
+        self.get(exercise).passed_at.is_some()
+    }
+
+    /// Records that an exercise was checked. Once it has passed, later
+    /// checks don't change anything.
+    pub fn record(&mut self, exercise: &Exercise, passed: bool) {
+        let entry = self.exercises.entry(exercise.package.clone()).or_default();
+        if entry.passed_at.is_some() {
+            return;
+        }
+        entry.attempts += 1;
+        if passed {
+            entry.passed_at = Some(now());
+        }
+    }
+
+    /// Reveals another of an exercise's hints (if there are any left), and
+    /// returns how many have been revealed.
+    pub fn use_hint(&mut self, exercise: &Exercise) -> usize {
+        let entry = self.exercises.entry(exercise.package.clone()).or_default();
+        entry.hints_used = (entry.hints_used + 1).min(exercise.hints.len());
+        entry.hints_used
+    }
+
+    /// The first exercise which isn't optional, and hasn't passed yet.
+    pub fn next<'e>(&self, exercises: &'e [Exercise]) -> Option<&'e Exercise> {
+        exercises
+            .iter()
+            .find(|exercise| !exercise.optional && !self.passed(exercise))
+    }
+
+    /// Prints a table of every exercise's progress.
+    pub fn print_status(&self, exercises: &[Exercise]) {
+        let width = crate::exercise::package_width(exercises);
+        println!(
+            "{:>2}  {:<width$} {:<11} {:>8} {:>5}  passed at",
+            "", "kata", "status", "attempts", "hints"
+        );
+        for exercise in exercises {
+            let progress = self.get(exercise);
+            let (status, passed_at) = match progress.passed_at {
+                Some(time) => ("passed", format_time(time)),
+                None if progress.attempts > 0 => ("in progress", String::new()),
+                None if exercise.optional => ("optional", String::new()),
+                None => ("not started", String::new()),
+            };
+            let hints = format!("{}/{}", progress.hints_used, exercise.hints.len());
+            let line = format!(
+                "{:>2}  {:<width$} {status:<11} {:>8} {hints:>5}  {passed_at}",
+                exercise.number, exercise.package, progress.attempts
+            );
+            println!("{}", line.trim_end());
+        }
+        let required: Vec<&Exercise> = exercises.iter().filter(|e| !e.optional).collect();
+        let passed = required.iter().filter(|e| self.passed(e)).count();
+        println!("\n{passed}/{} exercises passed.", required.len());
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM` (in UTC).
+pub fn format_time(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let (hour, minute) = (secs % 86400 / 3600, secs % 3600 / 60);
+
+    // Converts days since 1970-01-01 to a date; see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_attempts_until_passed() {
+        let exercises = [Exercise::for_test(2), Exercise::for_test(3)];
+        let mut progress = Progress::default();
+        assert_eq!(progress.next(&exercises).unwrap().number, 2);
+
+        progress.record(&exercises[0], false);
+        progress.record(&exercises[0], true);
+        progress.record(&exercises[0], false);
+        assert_eq!(progress.get(&exercises[0]).attempts, 2);
+        assert!(progress.passed(&exercises[0]));
+        assert_eq!(progress.next(&exercises).unwrap().number, 3);
+
+        let exercises = [
+            exercises[0].clone(),
+            Exercise {
+                optional: true,
+                ..Exercise::for_test(9)
+            },
+        ];
+        assert!(progress.next(&exercises).is_none());
+
+        let text = toml::to_string(&progress).unwrap();
+        assert_eq!(toml::from_str::<Progress>(&text).unwrap(), progress);
+    }
+
+    #[test]
+    fn reveals_hints_one_at_a_time() {
+        let mut exercise = Exercise::for_test(4);
+        exercise.hints = vec!["first".to_string(), "second".to_string()];
+        let mut progress = Progress::default();
+        assert_eq!(progress.use_hint(&exercise), 1);
+        assert_eq!(progress.use_hint(&exercise), 2);
+        assert_eq!(progress.use_hint(&exercise), 2);
+        assert_eq!(progress.get(&exercise).hints_used, 2);
+
+        let text = toml::to_string(&progress).unwrap();
+        assert_eq!(toml::from_str::<Progress>(&text).unwrap(), progress);
+    }
+
+    #[test]
+    fn formats_times() {
+        assert_eq!(format_time(0), "1970-01-01 00:00");
+        assert_eq!(format_time(951_827_696), "2000-02-29 12:34");
+    }
+}
@@ -0,0 +1,170 @@
+//! Puts an exercise back the way it was before the learner touched it.
+//!
+//! The first time the runner sees an exercise, it copies the directories the
+//! learner edits (see [`Exercise::source_dirs`]) into `.lifetimekata/originals`.
+//! Resetting copies them back, after saving the learner's attempt to
+//! `.lifetimekata/backups`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::exercise::{Exercise, Version};
+use crate::progress;
+use crate::Result;
+
+fn original_dir(root: &Path, exercise: &Exercise) -> PathBuf {
+    progress::state_dir(root)
+        .join("originals")
+        .join(&exercise.package)
+}
+
+/// Records the original sources of any exercises we haven't seen before.
+pub fn record_originals(root: &Path, exercises: &[Exercise]) -> Result<()> {
+    for exercise in exercises {
+        let crate_dir = root.join(exercise.crate_dir(Version::Exercise));
+        let original = original_dir(root, exercise);
+        for dir in exercise.source_dirs() {
+            if !original.join(&dir).exists() {
+                copy_dir(&crate_dir.join(&dir), &original.join(&dir))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Resets an exercise to its original sources, returning whether it did.
+///
+/// Unless `yes` is set, this asks for confirmation first.
+pub fn reset(root: &Path, exercise: &Exercise, yes: bool) -> Result<bool> {
+    let crate_dir = exercise.crate_dir(Version::Exercise);
+    let original = original_dir(root, exercise);
+    let dirs = exercise.source_dirs();
+
+    let mut unchanged = true;
+    for dir in &dirs {
+        unchanged &= read_dir_contents(&original.join(dir))?
+            == read_dir_contents(&root.join(&crate_dir).join(dir))?;
+    }
+    if unchanged {
+        println!("{} is already in its original state.", exercise.package);
+        return Ok(true);
+    }
+
+    let time = progress::format_time(progress::now()).replace([' ', ':'], "-");
+    let backups = progress::state_dir(root).join("backups");
+    let mut backup = backups.join(format!("{}-{time}", exercise.package));
+    for attempt in 2.. {
+        if !backup.exists() {
+            break;
+        }
+        backup = backups.join(format!("{}-{time}-{attempt}", exercise.package));
+    }
+    let relative_backup = backup.strip_prefix(root).unwrap_or(&backup);
+
+    if !yes {
+        let edited: Vec<String> = dirs
+            .iter()
+            .map(|dir| crate_dir.join(dir).display().to_string())
+            .collect();
+        print!(
+            "This will undo your changes to {}.\n\
+             (Your attempt will be saved to {}.)\n\
+             Continue? [y/N] ",
+            edited.join(" and "),
+            relative_backup.display(),
+        );
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            println!("Not resetting {}.", exercise.package);
+            return Ok(false);
+        }
+    }
+
+    for dir in &dirs {
+        let source = root.join(&crate_dir).join(dir);
+        copy_dir(&source, &backup.join(dir))?;
+        fs::remove_dir_all(&source)?;
+        copy_dir(&original.join(dir), &source)?;
+    }
+
+    println!(
+        "Reset {}. Your attempt was saved; to compare it, run:",
+        exercise.package
+    );
+    println!(
+        "    diff -r {} {}",
+        relative_backup.display(),
+        crate_dir.display()
+    );
+    Ok(true)
+}
+
+/// Copies a directory (recursively), creating `to` if it doesn't exist.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// The contents of every file under `dir`, by path relative to `dir`.
+fn read_dir_contents(dir: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>> {
+    let mut contents = BTreeMap::new();
+    let mut to_visit = vec![dir.to_path_buf()];
+    while let Some(current) = to_visit.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                to_visit.push(path);
+            } else {
+                let relative = path.strip_prefix(dir)?.to_path_buf();
+                contents.insert(relative, fs::read(&path)?);
+            }
+        }
+    }
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercise::Kind;
+
+    #[test]
+    fn resets_and_backs_up() {
+        let root = std::env::temp_dir().join(format!("lifetimekata-reset-{}", std::process::id()));
+        let exercise = Exercise {
+            dir: PathBuf::from("exercises/08_finale"),
+            kind: Kind::Bin,
+            ..Exercise::for_test(8)
+        };
+        let main_rs = root.join("exercises/08_finale/exercise/src/main.rs");
+        fs::create_dir_all(main_rs.parent().unwrap()).unwrap();
+        fs::write(&main_rs, "original").unwrap();
+
+        record_originals(&root, std::slice::from_ref(&exercise)).unwrap();
+        fs::write(&main_rs, "attempt").unwrap();
+        assert!(reset(&root, &exercise, true).unwrap());
+        assert_eq!(fs::read_to_string(&main_rs).unwrap(), "original");
+
+        let backups: Vec<_> = fs::read_dir(root.join(".lifetimekata/backups"))
+            .unwrap()
+            .collect();
+        assert_eq!(backups.len(), 1);
+        let backup = backups[0].as_ref().unwrap().path().join("src/main.rs");
+        assert_eq!(fs::read_to_string(backup).unwrap(), "attempt");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
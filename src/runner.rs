@@ -0,0 +1,257 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::exercise::{Check, Exercise, Kind, Version};
+use crate::{diagnostics, elide, Result};
+
+/// One part of checking an exercise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Build,
+    Test,
+    Run,
+    /// Check every function has the lifetimes the compiler would elide.
+    Elision,
+    /// Compile the exercise's error katas, and check they produce exactly
+    /// the errors they should.
+    Errors,
+}
+
+impl Step {
+    fn subcommand(self) -> &'static str {
+        match self {
+            Step::Build => "build",
+            Step::Test => "test",
+            Step::Run => "run",
+            Step::Elision => "have the elided lifetimes",
+            Step::Errors => "produce the expected errors",
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.subcommand())
+    }
+}
+
+/// The steps that check an exercise.
+///
+/// Everything is built first. Then it's tested or run, depending on the
+/// exercise's `check` in the manifest. Finally, the lifetimes are graded (if
+/// the manifest says to) and any error katas are checked.
+pub fn steps(exercise: &Exercise) -> Vec<Step> {
+    let mut steps = match exercise.check {
+        Check::Test => vec![Step::Build, Step::Test],
+        Check::Run => vec![Step::Build, Step::Run],
+    };
+    if exercise.grade_elision {
+        steps.push(Step::Elision);
+    }
+    if !exercise.error_katas.is_empty() {
+        steps.push(Step::Errors);
+    }
+    steps
+}
+
+/// The result of checking an exercise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    /// The first step that failed.
+    Failed(Step),
+}
+
+/// Whether to show the output of the cargo commands we run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Show,
+    Hide,
+}
+
+/// Runs every step for an exercise, stopping at the first failure.
+pub fn check(root: &Path, exercise: &Exercise) -> Result<Verdict> {
+    check_version(root, exercise, Version::Exercise, Output::Show)
+}
+
+/// Runs every step against one of an exercise's crates (the exercise or the
+/// solution), stopping at the first failure.
+pub fn check_version(
+    root: &Path,
+    exercise: &Exercise,
+    version: Version,
+    output: Output,
+) -> Result<Verdict> {
+    let package = exercise.package_for(version);
+    for step in steps(exercise) {
+        let passed = match step {
+            Step::Elision => grade_elision(root, exercise, version, output)?,
+            Step::Errors => check_error_katas(root, exercise, version, output)?,
+            _ => cargo_with_output(root, step, package, output)?,
+        };
+        if !passed {
+            return Ok(Verdict::Failed(step));
+        }
+    }
+    Ok(Verdict::Passed)
+}
+
+/// Grades the lifetimes in one of an exercise's crates against the ones in
+/// the solution, which writes out every lifetime the compiler would elide.
+/// (Grading against the exercise itself wouldn't work: the learner may have
+/// edited it before we ever saw it.)
+fn grade_elision(
+    root: &Path,
+    exercise: &Exercise,
+    version: Version,
+    output: Output,
+) -> Result<bool> {
+    let file = exercise.source_file();
+    let solution = fs::read_to_string(root.join(exercise.crate_dir(Version::Solution)).join(file))?;
+    let path = exercise.crate_dir(version).join(file);
+    let grades = elide::grade(&solution, &fs::read_to_string(root.join(&path))?)?;
+    if output == Output::Show {
+        println!("==> checking the lifetimes in {}", path.display());
+        for grade in &grades {
+            println!("{grade}");
+        }
+    }
+    Ok(grades.iter().all(|grade| grade.problem.is_none()))
+}
+
+/// Checks every error kata in one of an exercise's crates.
+fn check_error_katas(
+    root: &Path,
+    exercise: &Exercise,
+    version: Version,
+    output: Output,
+) -> Result<bool> {
+    let crate_dir = root.join(exercise.crate_dir(version));
+    let mut passed = true;
+    for kata in &exercise.error_katas {
+        let (report, rendered) = diagnostics::check_error_kata(&crate_dir, kata)?;
+        if output == Output::Show {
+            println!(
+                "==> checking the errors in {}",
+                exercise.crate_dir(version).join(&kata.file).display()
+            );
+            print!("{rendered}{report}");
+        }
+        passed &= report.passed();
+    }
+    Ok(passed)
+}
+
+/// Runs `cargo <step> --package <package>` in the workspace root,
+/// letting its output through. Returns whether it succeeded.
+pub fn cargo(root: &Path, step: Step, package: &str) -> Result<bool> {
+    cargo_with_output(root, step, package, Output::Show)
+}
+
+fn cargo_with_output(root: &Path, step: Step, package: &str, output: Output) -> Result<bool> {
+    let mut command = Command::new(cargo_path());
+    command
+        .arg(step.subcommand())
+        .arg("--package")
+        .arg(package)
+        .current_dir(root);
+    match output {
+        Output::Show => println!("==> cargo {step} --package {package}"),
+        Output::Hide => {
+            command.stdout(Stdio::null()).stderr(Stdio::null());
+        }
+    }
+    Ok(command.status()?.success())
+}
+
+/// The cargo we were run by, or whichever one is on the `PATH`.
+pub fn cargo_path() -> String {
+    std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string())
+}
+
+/// Prints a one-line summary of a check. If it passed, this also prints the
+/// exercise's explanation (if any); if not, where to get a hint.
+pub fn print_verdict(exercise: &Exercise, verdict: Verdict) {
+    match verdict {
+        Verdict::Passed => {
+            println!("✓ {} passed", exercise.package);
+            if let Some(explanation) = &exercise.explanation {
+                println!("\n{explanation}");
+            }
+        }
+        Verdict::Failed(Step::Elision) => println!(
+            "✗ {} failed: its lifetimes aren't the ones the compiler would elide",
+            exercise.package
+        ),
+        Verdict::Failed(Step::Errors) => println!(
+            "✗ {} failed: it didn't produce exactly the errors it should",
+            exercise.package
+        ),
+        Verdict::Failed(step) => println!(
+            "✗ {} failed: `cargo {step} --package {}` did not succeed",
+            exercise.package, exercise.package
+        ),
+    }
+    if verdict != Verdict::Passed && !exercise.hints.is_empty() {
+        println!(
+            "Stuck? `cargo run -- hint {}` gives you a hint.",
+            exercise.number
+        );
+    }
+}
+
+/// Builds and runs a binary exercise.
+pub fn run(root: &Path, exercise: &Exercise) -> Result<bool> {
+    if exercise.kind == Kind::Lib {
+        return Err(format!(
+            "{} is a library, so it can't be run; try `cargo run -- test {}`",
+            exercise.package, exercise.number
+        )
+        .into());
+    }
+    Ok(cargo(root, Step::Build, &exercise.package)? && cargo(root, Step::Run, &exercise.package)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn grades_elision_against_the_solution() {
+        let root = std::env::temp_dir().join(format!("lifetimekata-grade-{}", std::process::id()));
+        let exercise = Exercise {
+            dir: PathBuf::from("exercises/03_lifetime_elision"),
+            grade_elision: true,
+            ..Exercise::for_test(3)
+        };
+        let solution = fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("exercises/03_lifetime_elision/solutions/src/lib.rs"),
+        )
+        .unwrap();
+        // This compiles, but `example_b` and `example_d` share lifetimes
+        // the compiler would keep apart. It's the only version of the
+        // exercise on disk, so nothing can mistake it for the original.
+        let wrong = "\
+            pub fn example_a<'a>(_number: &'a i32) -> (&'a i32, &'a i32) { unimplemented!() }
+            pub fn example_b<'a>(_first_arg: &'a i32, _second_arg: &'a i32, \
+                                 _third_arg: &'a Option<&'a i32>) {}
+            pub fn example_c<'a>(_first_arg: &'a i32, _second_arg: &'a i32) -> &'a i32 { unimplemented!() }
+            pub fn example_d<'a>(_first_arg: &'a i32, _second_arg: &'a i32) -> &'a i32 { unimplemented!() }
+        "
+        .to_string();
+        for (version, source) in [(Version::Exercise, &wrong), (Version::Solution, &solution)] {
+            let src = root.join(exercise.crate_dir(version)).join("src");
+            fs::create_dir_all(&src).unwrap();
+            fs::write(src.join("lib.rs"), source).unwrap();
+        }
+
+        assert!(!grade_elision(&root, &exercise, Version::Exercise, Output::Hide).unwrap());
+        assert!(grade_elision(&root, &exercise, Version::Solution, Output::Hide).unwrap());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,577 @@
+//! Draws the scope of every local variable and borrow in a small snippet,
+//! like this:
+//!
+//! ```text
+//!                   num r  'a
+//! let num = 10;     ─┐
+//! let r = &num;      │  ─┐ ─┐
+//! println!("{r}");  ─┘  ─┘ ─┘
+//!
+//! 'a: `&num` on line 2
+//! ```
+//!
+//! Scopes are lexical: a local lasts until the end of the block it's
+//! declared in, and a borrow lasts as long as whatever it's stored in (a
+//! borrow passed to a function is assumed to end up in the result, just like
+//! the elision rules assume). Borrows which aren't stored anywhere last until
+//! the end of their statement. This is how the compiler worked before
+//! non-lexical lifetimes, so it's a simplification, but it's enough to see
+//! why something like "`num` is dropped here while still borrowed" happens.
+
+use std::fmt::Write;
+
+use proc_macro2::Span;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+use crate::Result;
+
+/// What a scope belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum What {
+    Local,
+    /// A borrow of the local with the given scope (an index into
+    /// [`Diagram::scopes`]), written as `text` (e.g. `&num`).
+    Borrow {
+        local: usize,
+        text: String,
+    },
+}
+
+/// The lines (counting from 1) something is alive on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    /// The local's name, or the borrow's lifetime (`'a`, `'b`, ...).
+    pub label: String,
+    pub what: What,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A snippet, and the scopes in it.
+#[derive(Debug, Clone)]
+pub struct Diagram {
+    pub lines: Vec<String>,
+    pub scopes: Vec<Scope>,
+}
+
+/// Where a value ends up, which decides how long a borrow in it lasts.
+#[derive(Debug, Clone, Copy)]
+enum Dest {
+    /// In the local with this scope.
+    Into(usize),
+    /// Nowhere: it's dropped at the end of this line.
+    Until(usize),
+}
+
+/// Works out the scopes in `source`: either whole functions, or the
+/// statements of one (like a doc-test).
+pub fn analyse(source: &str) -> Result<Diagram> {
+    let lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let mut analysis = Analysis::default();
+    match syn::parse_file(source) {
+        Ok(file) if !file.items.is_empty() => {
+            for item in &file.items {
+                if let syn::Item::Fn(function) = item {
+                    analysis.function(&function.sig, &function.block);
+                }
+            }
+        }
+        _ => {
+            // Statements: wrap them in a function, then take the line we
+            // added back off.
+            let wrapped = format!("fn snippet() {{\n{source}\n}}");
+            let function: syn::ItemFn = syn::parse_str(&wrapped)
+                .map_err(|err| format!("couldn't parse the snippet: {err}"))?;
+            analysis.line_offset = 1;
+            analysis.function(&function.sig, &function.block);
+            // The function's braces aren't part of the snippet.
+            for scope in &mut analysis.scopes {
+                scope.start = scope.start.clamp(1, lines.len());
+                scope.end = scope.end.clamp(1, lines.len());
+            }
+        }
+    }
+    Ok(Diagram {
+        lines,
+        scopes: analysis.scopes,
+    })
+}
+
+#[derive(Default)]
+struct Analysis {
+    scopes: Vec<Scope>,
+    /// The locals which are in scope, innermost last.
+    in_scope: Vec<(String, usize)>,
+    line_offset: usize,
+    borrows: usize,
+}
+
+impl Analysis {
+    fn line(&self, span: Span) -> usize {
+        span.start().line - self.line_offset
+    }
+
+    fn end_line(&self, span: Span) -> usize {
+        span.end().line - self.line_offset
+    }
+
+    fn function(&mut self, sig: &syn::Signature, body: &syn::Block) {
+        let end = self.line(body.brace_token.span.close());
+        let mark = self.in_scope.len();
+        for input in &sig.inputs {
+            if let syn::FnArg::Typed(typed) = input {
+                let start = self.line(typed.span());
+                self.bind(&typed.pat, start, end);
+            }
+        }
+        self.block(body, Dest::Until(end));
+        self.in_scope.truncate(mark);
+    }
+
+    /// Adds a scope for every name a pattern binds, without bringing them
+    /// into scope yet.
+    fn declare(&mut self, pat: &syn::Pat, start: usize, end: usize) -> Vec<(String, usize)> {
+        let mut names = vec![];
+        pattern_names(pat, &mut names);
+        names
+            .into_iter()
+            .map(|name| {
+                self.scopes.push(Scope {
+                    label: name.clone(),
+                    what: What::Local,
+                    start,
+                    end,
+                });
+                (name, self.scopes.len() - 1)
+            })
+            .collect()
+    }
+
+    fn bind(&mut self, pat: &syn::Pat, start: usize, end: usize) {
+        let declared = self.declare(pat, start, end);
+        self.in_scope.extend(declared);
+    }
+
+    fn block(&mut self, block: &syn::Block, dest: Dest) {
+        let end = self.line(block.brace_token.span.close());
+        let mark = self.in_scope.len();
+        for (index, stmt) in block.stmts.iter().enumerate() {
+            let statement_end = Dest::Until(self.end_line(stmt.span()));
+            match stmt {
+                syn::Stmt::Local(local) => {
+                    let start = self.line(local.let_token.span);
+                    let declared = self.declare(&local.pat, start, end);
+                    // The initializer can't see the new names, so they're
+                    // only brought into scope afterwards.
+                    if let Some(init) = &local.init {
+                        let dest = declared
+                            .first()
+                            .map_or(statement_end, |&(_, scope)| Dest::Into(scope));
+                        self.expr(&init.expr, dest);
+                        if let Some((_, diverge)) = &init.diverge {
+                            self.expr(diverge, statement_end);
+                        }
+                    }
+                    self.in_scope.extend(declared);
+                }
+                syn::Stmt::Expr(expr, None) if index + 1 == block.stmts.len() => {
+                    self.expr(expr, dest)
+                }
+                syn::Stmt::Expr(expr, _) => self.expr(expr, statement_end),
+                syn::Stmt::Macro(mac) => self.mac(&mac.mac, statement_end),
+                syn::Stmt::Item(_) => {}
+            }
+        }
+        self.in_scope.truncate(mark);
+    }
+
+    fn expr(&mut self, expr: &syn::Expr, dest: Dest) {
+        use syn::Expr;
+        let temporary = Dest::Until(self.end_line(expr.span()));
+        match expr {
+            Expr::Reference(reference) => {
+                self.borrow(expr, &reference.expr, dest);
+                self.expr(&reference.expr, dest);
+            }
+            Expr::Assign(assign) => {
+                let dest = match local_name(&assign.left).and_then(|name| self.resolve(&name)) {
+                    Some(local) => Dest::Into(local),
+                    None => dest,
+                };
+                self.expr(&assign.right, dest);
+            }
+            Expr::Block(block) => self.block(&block.block, dest),
+            Expr::Unsafe(block) => self.block(&block.block, dest),
+            Expr::If(if_expr) => {
+                self.expr(&if_expr.cond, temporary);
+                self.block(&if_expr.then_branch, dest);
+                if let Some((_, else_branch)) = &if_expr.else_branch {
+                    self.expr(else_branch, dest);
+                }
+            }
+            Expr::Match(match_expr) => {
+                self.expr(&match_expr.expr, temporary);
+                for arm in &match_expr.arms {
+                    self.expr(&arm.body, dest);
+                }
+            }
+            Expr::Call(call) => {
+                for arg in &call.args {
+                    self.expr(arg, dest);
+                }
+            }
+            Expr::MethodCall(call) => {
+                self.expr(&call.receiver, dest);
+                for arg in &call.args {
+                    self.expr(arg, dest);
+                }
+            }
+            Expr::Tuple(tuple) => {
+                for elem in &tuple.elems {
+                    self.expr(elem, dest);
+                }
+            }
+            Expr::Array(array) => {
+                for elem in &array.elems {
+                    self.expr(elem, dest);
+                }
+            }
+            Expr::Struct(structure) => {
+                for field in &structure.fields {
+                    self.expr(&field.expr, dest);
+                }
+            }
+            Expr::Paren(paren) => self.expr(&paren.expr, dest),
+            Expr::Group(group) => self.expr(&group.expr, dest),
+            Expr::Field(field) => self.expr(&field.base, dest),
+            Expr::Index(index) => {
+                self.expr(&index.expr, dest);
+                self.expr(&index.index, temporary);
+            }
+            Expr::Unary(unary) => self.expr(&unary.expr, dest),
+            Expr::Binary(binary) => {
+                self.expr(&binary.left, temporary);
+                self.expr(&binary.right, temporary);
+            }
+            Expr::Let(let_expr) => self.expr(&let_expr.expr, dest),
+            Expr::Return(ret) => {
+                if let Some(expr) = &ret.expr {
+                    self.expr(expr, temporary);
+                }
+            }
+            Expr::While(while_expr) => {
+                self.expr(&while_expr.cond, temporary);
+                self.block(&while_expr.body, temporary);
+            }
+            Expr::Loop(loop_expr) => self.block(&loop_expr.body, temporary),
+            Expr::ForLoop(for_loop) => {
+                self.expr(&for_loop.expr, temporary);
+                let mark = self.in_scope.len();
+                let start = self.line(for_loop.pat.span());
+                let end = self.line(for_loop.body.brace_token.span.close());
+                self.bind(&for_loop.pat, start, end);
+                self.block(&for_loop.body, temporary);
+                self.in_scope.truncate(mark);
+            }
+            Expr::Macro(mac) => self.mac(&mac.mac, dest),
+            _ => {}
+        }
+    }
+
+    /// Macros like `println!` and `assert_eq!` take expressions, so look
+    /// inside them if we can.
+    fn mac(&mut self, mac: &syn::Macro, dest: Dest) {
+        let parser = Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+        if let Ok(args) = mac.parse_body_with(parser) {
+            for arg in &args {
+                self.expr(arg, dest);
+            }
+        }
+    }
+
+    /// Records a borrow of `place` (if it's a local we know about).
+    fn borrow(&mut self, expr: &syn::Expr, place: &syn::Expr, dest: Dest) {
+        let Some(local) = local_name(place).and_then(|name| self.resolve(&name)) else {
+            return;
+        };
+        let label = lifetime_name(self.borrows);
+        self.borrows += 1;
+        let start = self.line(expr.span());
+        let end = match dest {
+            Dest::Into(scope) => self.scopes[scope].end,
+            Dest::Until(line) => line,
+        };
+        let text = expr.span().source_text().unwrap_or_default();
+        self.scopes.push(Scope {
+            label,
+            what: What::Borrow { local, text },
+            start,
+            end: end.max(start),
+        });
+    }
+
+    fn resolve(&self, name: &str) -> Option<usize> {
+        self.in_scope
+            .iter()
+            .rev()
+            .find(|(local, _)| local == name)
+            .map(|&(_, scope)| scope)
+    }
+}
+
+/// `'a`, `'b`, ..., `'z`, `'a1`, ...
+fn lifetime_name(index: usize) -> String {
+    let letter = (b'a' + (index % 26) as u8) as char;
+    match index / 26 {
+        0 => format!("'{letter}"),
+        round => format!("'{letter}{round}"),
+    }
+}
+
+/// The local a place expression (`x`, `x.field`, `x[0]`) belongs to.
+fn local_name(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Path(path) => path.path.get_ident().map(ToString::to_string),
+        syn::Expr::Field(field) => local_name(&field.base),
+        syn::Expr::Index(index) => local_name(&index.expr),
+        syn::Expr::Paren(paren) => local_name(&paren.expr),
+        _ => None,
+    }
+}
+
+fn pattern_names(pat: &syn::Pat, names: &mut Vec<String>) {
+    match pat {
+        syn::Pat::Ident(ident) => names.push(ident.ident.to_string()),
+        syn::Pat::Tuple(tuple) => tuple.elems.iter().for_each(|p| pattern_names(p, names)),
+        syn::Pat::TupleStruct(tuple) => tuple.elems.iter().for_each(|p| pattern_names(p, names)),
+        syn::Pat::Struct(structure) => structure
+            .fields
+            .iter()
+            .for_each(|field| pattern_names(&field.pat, names)),
+        syn::Pat::Reference(reference) => pattern_names(&reference.pat, names),
+        syn::Pat::Type(typed) => pattern_names(&typed.pat, names),
+        _ => {}
+    }
+}
+
+impl Diagram {
+    /// Borrows which last longer than the local they borrow.
+    pub fn problems(&self) -> Vec<String> {
+        self.scopes
+            .iter()
+            .filter_map(|scope| {
+                let What::Borrow { local, text } = &scope.what else {
+                    return None;
+                };
+                let local = &self.scopes[*local];
+                (scope.end > local.end).then(|| {
+                    format!(
+                        "`{}` (`{text}`) lasts until line {}, but `{}` is dropped at line {}.",
+                        scope.label, scope.end, local.label, local.end
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// What each borrow is, and any problems.
+    fn legend(&self) -> Vec<String> {
+        let mut legend: Vec<String> = self
+            .scopes
+            .iter()
+            .filter_map(|scope| match &scope.what {
+                What::Borrow { text, .. } => {
+                    Some(format!("{}: `{text}` on line {}", scope.label, scope.start))
+                }
+                What::Local => None,
+            })
+            .collect();
+        legend.extend(self.problems());
+        legend
+    }
+
+    /// The width of each scope's column.
+    fn column_widths(&self) -> Vec<usize> {
+        self.scopes
+            .iter()
+            .map(|scope| scope.label.chars().count().max(2) + 1)
+            .collect()
+    }
+
+    /// Draws the diagram as text, for a terminal.
+    pub fn render_text(&self) -> String {
+        let code_width = self.code_width();
+        let widths = self.column_widths();
+        let mut out = String::new();
+
+        let mut header = format!("{:code_width$}", "");
+        for (scope, width) in self.scopes.iter().zip(&widths) {
+            let _ = write!(header, "{:width$}", scope.label);
+        }
+        out.push_str(header.trim_end());
+        out.push('\n');
+
+        for (index, line) in self.lines.iter().enumerate() {
+            let mut row = format!("{line:code_width$}");
+            for (scope, width) in self.scopes.iter().zip(&widths) {
+                let _ = write!(row, "{:width$}", bracket(scope, index + 1));
+            }
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+
+        let legend = self.legend();
+        if !legend.is_empty() {
+            out.push('\n');
+        }
+        for line in legend {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Draws the diagram as an HTML `<figure>` containing an SVG, which can
+    /// be pasted into the book.
+    pub fn render_html(&self) -> String {
+        const CHAR: usize = 9;
+        const LINE: usize = 20;
+        let code_width = self.code_width();
+        let widths = self.column_widths();
+        let width = (code_width + widths.iter().sum::<usize>()) * CHAR;
+        let height = (self.lines.len() + 1) * LINE + LINE / 2;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="monospace" font-size="14">"#
+        );
+        for (index, line) in self.lines.iter().enumerate() {
+            let _ = writeln!(
+                svg,
+                r#"  <text x="0" y="{}" xml:space="preserve">{}</text>"#,
+                (index + 2) * LINE,
+                escape(line)
+            );
+        }
+        let mut column = code_width;
+        for (scope, width) in self.scopes.iter().zip(&widths) {
+            let colour = match scope.what {
+                What::Local => "currentColor",
+                What::Borrow { .. } => "#c0392b",
+            };
+            let x = column * CHAR;
+            let _ = writeln!(
+                svg,
+                r#"  <text x="{x}" y="{LINE}" fill="{colour}">{}</text>"#,
+                escape(&scope.label)
+            );
+            // The middle of the first and last lines.
+            let top = (scope.start + 1) * LINE - LINE / 3;
+            let bottom = (scope.end + 1) * LINE - LINE / 3;
+            let _ = writeln!(
+                svg,
+                r#"  <path d="M {x} {top} H {} V {bottom} H {x}" fill="none" stroke="{colour}"/>"#,
+                x + CHAR
+            );
+            column += width;
+        }
+        svg.push_str("</svg>\n");
+
+        let mut html = String::from("<figure class=\"lifetime-diagram\">\n");
+        html.push_str(&svg);
+        let legend = self.legend();
+        if !legend.is_empty() {
+            html.push_str("<figcaption>\n");
+            for line in legend {
+                let _ = writeln!(html, "  <p>{}</p>", escape(&line));
+            }
+            html.push_str("</figcaption>\n");
+        }
+        html.push_str("</figure>\n");
+        html
+    }
+
+    fn code_width(&self) -> usize {
+        self.lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+            + 2
+    }
+}
+
+/// The part of a scope's bracket on a line.
+fn bracket(scope: &Scope, line: usize) -> &'static str {
+    match (line == scope.start, line == scope.end) {
+        (true, true) => "──",
+        (true, false) => "─┐",
+        (false, true) => "─┘",
+        _ if scope.start < line && line < scope.end => " │",
+        _ => "",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_borrows_which_outlive_locals() {
+        // The `compile_fail` example from ex02's `only_if_greater_hard`.
+        let snippet = "\
+let greater_than = 7;
+let otherwise = -1;
+let answer = {
+    let num = 10;
+    only_if_greater_hard(&num, &greater_than, &otherwise)
+    // num is dropped here
+};
+assert_eq!(answer, &10);";
+        let diagram = analyse(snippet).unwrap();
+        let spans: Vec<(&str, usize, usize)> = diagram
+            .scopes
+            .iter()
+            .map(|scope| (scope.label.as_str(), scope.start, scope.end))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("greater_than", 1, 8),
+                ("otherwise", 2, 8),
+                ("answer", 3, 8),
+                ("num", 4, 7),
+                ("'a", 5, 8),
+                ("'b", 5, 8),
+                ("'c", 5, 8),
+            ]
+        );
+        assert_eq!(
+            diagram.problems(),
+            vec!["`'a` (`&num`) lasts until line 8, but `num` is dropped at line 7."]
+        );
+        assert!(diagram.render_html().contains("&amp;num"));
+    }
+
+    #[test]
+    fn renders_brackets() {
+        let diagram = analyse("let num = 10;\nlet r = &num;\nprintln!(\"{r}\");").unwrap();
+        assert_eq!(
+            diagram.render_text(),
+            "                  num r  'a\n\
+             let num = 10;     ─┐\n\
+             let r = &num;      │  ─┐ ─┐\n\
+             println!(\"{r}\");  ─┘  ─┘ ─┘\n\
+             \n\
+             'a: `&num` on line 2\n"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Checks that the exercises and their solutions are still in sync.
+//!
+//! For every exercise, this checks that:
+//!
+//!  - the solution passes,
+//!  - the unsolved exercise fails at the step we expect it to (and, if it
+//!    fails to build, with the errors in its `unsolved.errors` snapshot),
+//!  - the unsolved error katas don't already produce their errors, and
+//!  - the test module (which is copied between the exercise and the
+//!    solution) is the same in both.
+//!
+//! It also checks that the fun examples still produce the errors they're
+//! meant to (see [`crate::explore`]).
+
+use std::fs;
+use std::path::Path;
+
+use crate::diagnostics;
+use crate::diff;
+use crate::exercise::{Exercise, Unsolved, Version};
+use crate::explore;
+use crate::runner::{self, Output, Step, Verdict};
+use crate::Result;
+
+/// How an unsolved exercise should fail.
+fn expected_verdict(exercise: &Exercise) -> Verdict {
+    match exercise.unsolved {
+        Unsolved::FailsToBuild => Verdict::Failed(Step::Build),
+        Unsolved::FailsTests => Verdict::Failed(Step::Test),
+        Unsolved::Passes => Verdict::Passed,
+    }
+}
+
+/// Describes what happened, e.g. "fails to build".
+fn describe(verdict: Verdict) -> String {
+    match verdict {
+        Verdict::Passed => "passes".to_string(),
+        Verdict::Failed(step) => format!("fails to {step}"),
+    }
+}
+
+/// Describes what should happen, e.g. "fail to build".
+fn describe_expected(verdict: Verdict) -> String {
+    match verdict {
+        Verdict::Passed => "pass".to_string(),
+        Verdict::Failed(step) => format!("fail to {step}"),
+    }
+}
+
+/// Describes how the errors something produces differ from its snapshot.
+fn describe_changed_errors(what: &str, expected: &str, actual: &str) -> String {
+    let indent = |errors: &str| -> String {
+        errors
+            .lines()
+            .map(|line| format!("\n      {line}"))
+            .collect()
+    };
+    format!(
+        "{what} should produce:{}\n    but it produces:{}",
+        indent(expected),
+        indent(actual)
+    )
+}
+
+/// Returns the `#[cfg(test)]` module at the end of a source file, if any.
+fn test_module(source: &str) -> Option<&str> {
+    source.find("#[cfg(test)]").map(|start| &source[start..])
+}
+
+/// Verifies every exercise, printing a line for each. Returns whether they
+/// were all correct.
+pub fn verify_solutions(root: &Path, exercises: &[Exercise]) -> Result<bool> {
+    let mut all_ok = true;
+    for exercise in exercises {
+        let problems = verify(root, exercise)?;
+        if problems.is_empty() {
+            println!("✓ {}", exercise.package);
+        } else {
+            println!("✗ {}", exercise.package);
+            for problem in problems {
+                println!("    {problem}");
+            }
+            all_ok = false;
+        }
+    }
+
+    for example in explore::load(root)? {
+        let outcome = explore::check(root, &example)?;
+        if outcome.matches() {
+            println!("✓ {}", example.name());
+        } else {
+            println!("✗ {}", example.name());
+            println!(
+                "    {}",
+                describe_changed_errors("it", &outcome.expected, &outcome.actual)
+            );
+            all_ok = false;
+        }
+    }
+    Ok(all_ok)
+}
+
+/// Returns everything wrong with one exercise.
+fn verify(root: &Path, exercise: &Exercise) -> Result<Vec<String>> {
+    let mut problems = vec![];
+
+    let solution = runner::check_version(root, exercise, Version::Solution, Output::Hide)?;
+    if solution != Verdict::Passed {
+        problems.push(format!(
+            "the solution {}, but it should pass",
+            describe(solution)
+        ));
+    }
+
+    let expected = expected_verdict(exercise);
+    let unsolved = runner::check_version(root, exercise, Version::Exercise, Output::Hide)?;
+    if unsolved != expected {
+        problems.push(format!(
+            "the exercise {}, but it should {}",
+            describe(unsolved),
+            describe_expected(expected)
+        ));
+    }
+
+    // The book refers to the errors the unsolved exercises produce, so check
+    // they haven't changed.
+    if exercise.unsolved == Unsolved::FailsToBuild {
+        let diagnostics = diagnostics::compile_package(root, &exercise.package)?;
+        let actual = diagnostics::snapshot(root, &diagnostics)?;
+        let path = root.join(exercise.snapshot_path());
+        if diagnostics::blessing() {
+            fs::write(&path, &actual)?;
+        }
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        if actual != expected {
+            problems.push(describe_changed_errors("the exercise", &expected, &actual));
+        }
+    }
+
+    // The exercise usually fails before its error katas are checked, so
+    // check them separately.
+    let exercise_dir = root.join(exercise.crate_dir(Version::Exercise));
+    for kata in &exercise.error_katas {
+        if diagnostics::check_error_kata(&exercise_dir, kata)?
+            .0
+            .passed()
+        {
+            problems.push(format!(
+                "the unsolved {} already produces the expected errors",
+                kata.file.display()
+            ));
+        }
+    }
+
+    let file = exercise.source_file();
+    let exercise_source = fs::read_to_string(root.join(&exercise.dir).join("exercise").join(file))?;
+    let solution_source =
+        fs::read_to_string(root.join(&exercise.dir).join("solutions").join(file))?
+            .replace(&exercise.solution_package, &exercise.package);
+    match (test_module(&exercise_source), test_module(&solution_source)) {
+        (None, None) => {}
+        (Some(exercise_tests), Some(solution_tests)) => {
+            if !diff::same_tokens(exercise_tests, solution_tests) {
+                problems.push(format!(
+                    "the tests in the exercise and solution {file} differ"
+                ));
+            }
+        }
+        (Some(_), None) => problems.push(format!("the solution {file} is missing the tests")),
+        (None, Some(_)) => problems.push(format!("the exercise {file} is missing the tests")),
+    }
+
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_test_modules() {
+        let source = "fn main() {}\n\n#[cfg(test)]\nmod test {\n}\n";
+        assert_eq!(test_module(source), Some("#[cfg(test)]\nmod test {\n}\n"));
+        assert_eq!(test_module("fn main() {}"), None);
+    }
+}
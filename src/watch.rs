@@ -0,0 +1,140 @@
+//! Re-checks an exercise every time one of its files is saved.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::exercise::{Exercise, Version};
+use crate::progress::Progress;
+use crate::runner::{self, Verdict};
+use crate::Result;
+
+/// How often we look for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches an exercise, re-checking it whenever its sources change.
+///
+/// If `number` is `None`, we watch the next unfinished exercise, and move on
+/// to the one after it when it passes. This only returns once every
+/// exercise has passed (or on an error); otherwise, quit with Ctrl-C.
+pub fn watch(root: &Path, exercises: &[Exercise], number: Option<u32>) -> Result<bool> {
+    let mut progress = Progress::load(root)?;
+    let mut exercise = match number {
+        Some(number) => crate::exercise::find(exercises, number)?,
+        None => match progress.next(exercises) {
+            Some(exercise) => exercise,
+            None => {
+                println!("You've passed every exercise. Congratulations!");
+                return Ok(true);
+            }
+        },
+    };
+
+    let mut dirs = watched_dirs(root, exercise);
+    loop {
+        // This is taken before checking, so that saving during a check
+        // still triggers another one.
+        let mut before = snapshot(&dirs)?;
+
+        // Clear the screen, so only the latest output is visible.
+        print!("\x1b[2J\x1b[H");
+        println!("Watching {} (press Ctrl-C to stop)\n", exercise.package);
+
+        let verdict = runner::check(root, exercise)?;
+        println!();
+        runner::print_verdict(exercise, verdict);
+        progress.record(exercise, verdict == Verdict::Passed);
+        progress.save(root)?;
+
+        if verdict == Verdict::Passed && number.is_none() {
+            match progress.next(exercises) {
+                Some(next) => {
+                    exercise = next;
+                    dirs = watched_dirs(root, exercise);
+                    before = snapshot(&dirs)?;
+                    println!(
+                        "Moving on to {}; save one of its files to check it.",
+                        exercise.package
+                    );
+                }
+                None => {
+                    println!("You've passed every exercise. Congratulations!");
+                    return Ok(true);
+                }
+            }
+        }
+
+        wait_for_change(&dirs, &before)?;
+    }
+}
+
+/// The directories of an exercise's sources.
+fn watched_dirs(root: &Path, exercise: &Exercise) -> Vec<PathBuf> {
+    let crate_dir = root.join(exercise.crate_dir(Version::Exercise));
+    exercise
+        .source_dirs()
+        .iter()
+        .map(|dir| crate_dir.join(dir))
+        .collect()
+}
+
+/// The last-modified time of every file under each of `dirs`.
+fn snapshot(dirs: &[PathBuf]) -> Result<Vec<BTreeMap<PathBuf, SystemTime>>> {
+    dirs.iter().map(|dir| modified_times(dir)).collect()
+}
+
+/// Blocks until any file under `dirs` has been created, removed or modified
+/// since `before` was taken.
+fn wait_for_change(dirs: &[PathBuf], before: &[BTreeMap<PathBuf, SystemTime>]) -> Result<()> {
+    loop {
+        if snapshot(dirs)? != before {
+            return Ok(());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// The last-modified time of every file under `dir`.
+fn modified_times(dir: &Path) -> Result<BTreeMap<PathBuf, SystemTime>> {
+    let mut times = BTreeMap::new();
+    let mut to_visit = vec![dir.to_path_buf()];
+    while let Some(dir) = to_visit.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                to_visit.push(entry.path());
+            } else {
+                times.insert(entry.path(), metadata.modified()?);
+            }
+        }
+    }
+    Ok(times)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notices_new_and_changed_files() {
+        let dir = std::env::temp_dir().join(format!("lifetimekata-watch-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+
+        let before = modified_times(&dir).unwrap();
+        fs::write(dir.join("nested/lib.rs"), "fn main() {}").unwrap();
+        let after = modified_times(&dir).unwrap();
+        assert_ne!(before, after);
+        assert!(after.contains_key(&dir.join("nested/lib.rs")));
+
+        // A change made before waiting (e.g. during a check) still counts.
+        let dirs = [dir.clone()];
+        let before = snapshot(&dirs).unwrap();
+        fs::write(dir.join("nested/main.rs"), "fn main() {}").unwrap();
+        wait_for_change(&dirs, &before).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
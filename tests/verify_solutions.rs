@@ -0,0 +1,16 @@
+//! Checks that every solution passes, and every exercise fails until it is
+//! solved. This is the same as running `lifetimekata verify-solutions`.
+
+use std::process::Command;
+
+#[test]
+fn solutions_pass_and_exercises_fail() {
+    let output = Command::new(env!("CARGO_BIN_EXE_lifetimekata"))
+        .arg("verify-solutions")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+}